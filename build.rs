@@ -0,0 +1,108 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn main() {
+    // The template tree is embedded with `include_dir!`, so rebuild when it changes
+    println!("cargo:rerun-if-changed=template");
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    // Embed a copy holding only the files the template is made of, so lock
+    // files and build artifacts of the local checkout never end up in the binary
+    let template = out_dir.join("template");
+    if template.exists() {
+        fs::remove_dir_all(&template).unwrap();
+    }
+    let mut files = template_files(Path::new("template"));
+    files.sort();
+
+    // `include_dir!` does not keep file permissions, so record whether every
+    // template file is executable for the embedded template source
+    let mut modes = Vec::new();
+    for path in &files {
+        let source = Path::new("template").join(path);
+        let target = template.join(path);
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::copy(&source, &target).unwrap();
+        let metadata = fs::metadata(&source).unwrap();
+        modes.push((path.to_string_lossy().into_owned(), file_mode(&metadata)));
+    }
+    // `include_dir!` needs the directory even if the template is empty
+    fs::create_dir_all(&template).unwrap();
+
+    let entries: String = modes
+        .iter()
+        .map(|(path, mode)| format!("    ({:?}, 0o{:o}),\n", path, mode))
+        .collect();
+    fs::write(
+        out_dir.join("template_modes.rs"),
+        format!("&[\n{}]\n", entries),
+    )
+    .unwrap();
+}
+
+/// Lists the template files, relative to the template directory
+///
+/// In a git checkout these are the tracked files, which leaves out ignored
+/// ones such as `Cargo.lock` and `target/`. Packaged sources carry no git
+/// metadata but are already stripped of ignored files by `cargo package`,
+/// so every file is taken apart from `target/` directories.
+fn template_files(root: &Path) -> Vec<PathBuf> {
+    let tracked = Command::new("git")
+        .args(["ls-files", "-z", "--cached", "--", "."])
+        .current_dir(root)
+        .output();
+    match tracked {
+        Ok(output) if output.status.success() && !output.stdout.is_empty() => output
+            .stdout
+            .split(|byte| *byte == 0)
+            .filter(|path| !path.is_empty())
+            .map(|path| PathBuf::from(String::from_utf8_lossy(path).into_owned()))
+            // Deleted from the working tree but not yet from the index
+            .filter(|path| root.join(path).is_file())
+            .collect(),
+        _ => {
+            let mut files = Vec::new();
+            collect_files(root, Path::new(""), &mut files);
+            files
+        }
+    }
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(root.join(dir)) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = dir.join(entry.file_name());
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            if entry.file_name() != "target" {
+                collect_files(root, &path, files);
+            }
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+/// Normalizes the mode so the generated files do not depend on the umask of
+/// the machine gepetto was built on
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    if metadata.permissions().mode() & 0o111 != 0 {
+        0o755
+    } else {
+        0o644
+    }
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &fs::Metadata) -> u32 {
+    0o644
+}
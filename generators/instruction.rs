@@ -0,0 +1,82 @@
+{{ imports }}
+
+pub struct {{ type_name }}Accounts<'a> {
+{%- for account in accounts %}
+    pub {{ account.name }}: &'a AccountInfo,
+{%- endfor %}
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for {{ type_name }}Accounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [{{ account_names | join(sep=", ") }}] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+{% for account in accounts %}{% if account.checks %}
+{% for check in account.checks %}        {{ check }}
+{% endfor %}{% endif %}{% endfor %}
+        Ok(Self { {{ account_names | join(sep=", ") }} })
+    }
+}
+{%- if args %}
+
+pub struct {{ type_name }}InstructionData {
+{%- for arg in args %}
+    pub {{ arg.name }}: {{ arg.ty }},
+{%- endfor %}
+}
+
+impl TryFrom<&[u8]> for {{ type_name }}InstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != {{ data_len }} {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+{% for arg in args %}        {{ arg.parse }}
+{% endfor %}        Ok(Self { {{ arg_names | join(sep=", ") }} })
+    }
+}
+{%- endif %}
+
+pub struct {{ type_name }}<'a> {
+    pub accounts: {{ type_name }}Accounts<'a>,
+{%- if args %}
+    pub instruction_data: {{ type_name }}InstructionData,
+{%- endif %}
+}
+{% if args %}
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for {{ type_name }}<'a> {
+    type Error = ProgramError;
+
+    fn try_from(
+        (instruction_data, accounts): (&'a [u8], &'a [AccountInfo]),
+    ) -> Result<Self, Self::Error> {
+        let accounts = {{ type_name }}Accounts::try_from(accounts)?;
+        let instruction_data = {{ type_name }}InstructionData::try_from(instruction_data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+{% else %}
+impl<'a> TryFrom<&'a [AccountInfo]> for {{ type_name }}<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = {{ type_name }}Accounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+{% endif %}
+impl<'a> {{ type_name }}<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &{{ discriminator }};
+
+    pub fn process(&mut self) -> Result<(), ProgramError> {
+        // TODO: implement the {{ name }} instruction
+        Ok(())
+    }
+}
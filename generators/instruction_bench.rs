@@ -0,0 +1,39 @@
+/// Helper function to build the {{ name }} instruction and its accounts
+fn build_{{ name }}_instruction() -> (Instruction, Vec<(Pubkey, Account)>) {
+{%- if uses_system_program %}
+    let (system_program, {% if not uses_system_account %}_{% endif %}system_account) = program::keyed_account_for_system_program();
+{%- endif %}
+{%- for account in accounts %}{% if not account.system %}
+{%- if account.pda %}
+    let ({{ account.name }}, _) = Pubkey::find_program_address(&[b"{{ account.name }}"], &PROGRAM);
+{%- else %}
+    let {{ account.name }} = Pubkey::new_unique();
+{%- endif %}
+{%- endif %}{% endfor %}
+{% if args %}
+    let mut data = vec![*{{ type_name }}::DISCRIMINATOR];
+{%- for arg in args %}
+    data.extend_from_slice(&{{ arg.sample }}); // {{ arg.name }}
+{%- endfor %}
+{% endif %}
+    let instruction = Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+{%- for account in accounts %}
+            {{ account.meta }},
+{%- endfor %}
+        ],
+        {% if args %}data{% else %}data: vec![*{{ type_name }}::DISCRIMINATOR]{% endif %},
+    };
+    let accounts = vec![
+{%- for account in accounts %}
+{%- if account.system %}
+        (system_program, system_account),
+{%- else %}
+        ({{ account.name }}, Account::new(1_000_000_000, 0, &{% if account.program %}PROGRAM{% else %}system_program{% endif %})),
+{%- endif %}
+{%- endfor %}
+    ];
+    (instruction, accounts)
+}
+
@@ -0,0 +1,52 @@
+
+#[test]
+fn test_{{ name }}() {
+    let mollusk = mollusk();
+{% if uses_system_program %}
+    //system program and system account
+    let (system_program, {% if not uses_system_account %}_{% endif %}system_account) = program::keyed_account_for_system_program();
+{% endif %}
+    //Initialize the accounts
+{%- for account in accounts %}{% if not account.system %}
+{%- if account.pda %}
+    let ({{ account.name }}, _{{ account.name }}_bump) = Pubkey::find_program_address(&[b"{{ account.name }}"], &PROGRAM);
+{%- else %}
+    let {{ account.name }} = Pubkey::new_unique();
+{%- endif %}
+    let {{ account.name }}_account = Account::new(1 * LAMPORTS_PER_SOL, 0, &{% if account.program %}PROGRAM{% else %}system_program{% endif %});
+{%- endif %}{% endfor %}
+
+    //Push the accounts in to the instruction_accounts vec!
+    let ix_accounts = vec![
+{%- for account in accounts %}
+        {{ account.meta }}, // {{ account.name }}
+{%- endfor %}
+    ];
+{% if args %}
+    let mut ix_data = vec![*{{ type_name }}::DISCRIMINATOR];
+{%- for arg in args %}
+    ix_data.extend_from_slice(&{{ arg.sample }}); // {{ arg.name }}
+{%- endfor %}
+{%- else %}
+    let ix_data = vec![*{{ type_name }}::DISCRIMINATOR];
+{%- endif %}
+
+    // Create instruction
+    let instruction = Instruction::new_with_bytes(PROGRAM, &ix_data, ix_accounts);
+
+    // Create tx_accounts vec
+    let tx_accounts = &vec![
+{%- for account in accounts %}
+{%- if account.system %}
+        (system_program, system_account.clone()),
+{%- else %}
+        ({{ account.name }}, {{ account.name }}_account.clone()),
+{%- endif %}
+{%- endfor %}
+    ];
+
+    let {{ name }}_res =
+        mollusk.process_and_validate_instruction(&instruction, tx_accounts, &[Check::success()]);
+
+    assert!({{ name }}_res.program_result == ProgramResult::Success);
+}
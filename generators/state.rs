@@ -0,0 +1,86 @@
+{{ imports }}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct {{ type_name }} {
+    pub key: StateKey,
+{%- if pda %}
+    pub bump: u8,
+{%- endif %}
+{%- for field in fields %}
+    pub {{ field.name }}: {{ field.storage }},
+{%- endfor %}
+}
+
+impl {{ type_name }} {
+    pub const LEN: usize = size_of::<StateKey>(){% if pda %} + size_of::<u8>(){% endif %}{% for field in fields %} + size_of::<{{ field.storage }}>(){% endfor %};
+
+    #[inline(always)]
+    pub fn load_mut(bytes: &mut [u8], check_key: bool) -> Result<&mut Self, ProgramError> {
+        if bytes.len() != {{ type_name }}::LEN || (check_key && bytes[0] != StateKey::{{ type_name }} as u8) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &mut *core::mem::transmute::<*mut u8, *mut Self>(bytes.as_mut_ptr()) })
+    }
+
+    #[inline(always)]
+    pub fn load(bytes: &[u8]) -> Result<&Self, ProgramError> {
+        if bytes.len() != {{ type_name }}::LEN || bytes[0] != StateKey::{{ type_name }} as u8 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(unsafe { &*core::mem::transmute::<*const u8, *const Self>(bytes.as_ptr()) })
+    }
+
+    #[inline(always)]
+{%- if set_inner_params | length > 7 %}
+    #[allow(clippy::too_many_arguments)]
+{%- endif %}
+    pub fn set_inner(&mut self{% for field in fields %}, {{ field.name }}: {{ field.ty }}{% endfor %}{% if pda %}, bump: u8{% endif %}) {
+        self.key = StateKey::{{ type_name }};
+{%- if pda %}
+        self.bump = bump;
+{%- endif %}
+{%- for field in fields %}
+{%- if field.accessors %}
+        self.set_{{ field.name }}({{ field.name }});
+{%- else %}
+        self.{{ field.name }} = {{ field.name }};
+{%- endif %}
+{%- endfor %}
+    }
+{%- for field in fields %}{% if field.accessors %}
+
+    #[inline(always)]
+    pub fn {{ field.name }}(&self) -> {{ field.ty }} {
+        {{ field.getter }}
+    }
+
+    #[inline(always)]
+    pub fn set_{{ field.name }}(&mut self, {{ field.name }}: {{ field.ty }}) {
+        {{ field.setter }}
+    }
+{%- endif %}{% endfor %}
+{%- if pda %}
+
+    #[inline(always)]
+    pub fn seeds{{ seeds_signature }} {
+        [{{ seed_items | join(sep=", ") }}]
+    }
+
+    #[inline(always)]
+    pub fn seeds_with_bump{{ with_bump_signature }} {
+        let seeds = Self::seeds({{ seed_args }});
+        [{% for i in range(end=seed_count) %}seeds[{{ i }}], {% endfor %}bump]
+    }
+
+    #[inline(always)]
+    pub fn signer_seeds_with_bump{{ signer_signature }} {
+        let seeds = Self::seeds_with_bump({{ seed_args_with_bump }});
+        [
+{%- for i in range(end=seed_count + 1) %}
+            Seed::from(seeds[{{ i }}]),
+{%- endfor %}
+        ]
+    }
+{%- endif %}
+}
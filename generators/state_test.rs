@@ -0,0 +1,40 @@
+#[test]
+fn test_{{ name }}_layout() {
+    // Sample values of every field
+{%- for field in fields %}
+    let {{ field.name }} = {{ field.sample }};
+{%- endfor %}
+{%- if pda %}
+    let bump = 255;
+{%- endif %}
+
+    // Write the state into account data of its length
+    let mut data = [0u8; {{ type_name }}::LEN];
+    {{ type_name }}::load_mut(&mut data, false)
+        .unwrap()
+        .set_inner({{ set_inner_params | join(sep=", ") }});
+
+    // Every field is stored at its offset, the fields covering the whole data
+    assert_eq!({{ type_name }}::LEN, {{ len }});
+    assert_eq!(core::mem::size_of::<{{ type_name }}>(), {{ type_name }}::LEN);
+    assert_eq!(data[0], StateKey::{{ type_name }} as u8);
+{%- if pda %}
+    assert_eq!(data[1], bump);
+{%- endif %}
+{%- for field in fields %}
+    assert_eq!(&data[{{ field.start }}..{{ field.end }}], &{{ field.bytes }});
+{%- endfor %}
+
+    // Every field reads back as written
+    let state = {{ type_name }}::load(&data).unwrap();
+{%- if pda %}
+    assert_eq!(state.bump, bump);
+{%- endif %}
+{%- for field in fields %}
+{%- if field.accessors %}
+    assert_eq!(state.{{ field.name }}(), {{ field.name }});
+{%- else %}
+    assert_eq!(state.{{ field.name }}, {{ field.name }});
+{%- endif %}
+{%- endfor %}
+}
@@ -0,0 +1,176 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use std::path::Path;
+
+use crate::license::License;
+
+/// Prefix of the environment variables answering template variables
+const VARIABLE_ENV_PREFIX: &str = "GEPETTO_VAR_";
+
+/// Answers given ahead of time, used instead of prompting
+///
+/// Values come from command-line flags, `GEPETTO_*` environment variables
+/// and an answers file, in that order of precedence. When the session is not
+/// interactive, a value that is neither answered nor has a default is an
+/// error instead of a prompt.
+#[derive(Debug, Clone)]
+pub struct Answers {
+    /// Package name from the answers file, used when none is given otherwise
+    pub name: Option<String>,
+    /// Company name for the license and Cargo metadata
+    pub company: Option<String>,
+    /// License of the project
+    pub license: Option<License>,
+    /// Whether generated Rust files start with an SPDX license header
+    pub spdx_headers: bool,
+    /// Template variables given with `--var`
+    variables: BTreeMap<String, String>,
+    /// Template variables from the answers file
+    file_variables: BTreeMap<String, String>,
+    /// Whether prompts may be shown
+    pub interactive: bool,
+}
+
+/// Contents of an answers file, e.g. `gepetto new --answers answers.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AnswersFile {
+    name: Option<String>,
+    company: Option<String>,
+    license: Option<String>,
+    spdx_headers: Option<bool>,
+    #[serde(default)]
+    variables: BTreeMap<String, toml::Value>,
+}
+
+impl Default for Answers {
+    fn default() -> Self {
+        Answers {
+            name: None,
+            company: None,
+            license: None,
+            spdx_headers: false,
+            variables: BTreeMap::new(),
+            file_variables: BTreeMap::new(),
+            interactive: std::io::stdin().is_terminal(),
+        }
+    }
+}
+
+impl Answers {
+    /// Combines the answers given on the command line with an answers file
+    ///
+    /// # Arguments
+    /// * `company` - Company name from `--company` or `GEPETTO_COMPANY`
+    /// * `license` - License from `--license` or `GEPETTO_LICENSE`
+    /// * `spdx_headers` - Whether `--spdx-headers` was passed
+    /// * `variables` - `name=value` pairs from `--var`
+    /// * `answers_file` - Optional TOML file with more answers
+    /// * `no_input` - Never prompt, even when stdin is a terminal
+    ///
+    /// # Returns
+    /// The merged answers
+    pub fn new(
+        company: Option<String>,
+        license: Option<License>,
+        spdx_headers: bool,
+        variables: &[String],
+        answers_file: Option<&Path>,
+        no_input: bool,
+    ) -> Result<Self> {
+        let file = match answers_file {
+            Some(path) => AnswersFile::load(path)?,
+            None => AnswersFile::default(),
+        };
+
+        let variables = variables
+            .iter()
+            .map(|pair| {
+                pair.split_once('=')
+                    .map(|(name, value)| (name.trim().to_string(), value.to_string()))
+                    .ok_or_else(|| anyhow::anyhow!("Invalid --var '{}', expected NAME=VALUE", pair))
+            })
+            .collect::<Result<_>>()?;
+
+        let file_variables = file
+            .variables
+            .into_iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    toml::Value::String(s) => s,
+                    toml::Value::Integer(i) => i.to_string(),
+                    toml::Value::Boolean(b) => b.to_string(),
+                    _ => {
+                        return Err(anyhow::anyhow!(
+                            "Variable '{}' in the answers file must be a string, integer or bool",
+                            name
+                        ))
+                    }
+                };
+                Ok((name, value))
+            })
+            .collect::<Result<_>>()?;
+
+        let license = match license {
+            Some(license) => Some(license),
+            None => file.license.as_deref().map(str::parse).transpose()?,
+        };
+
+        Ok(Answers {
+            name: file.name,
+            company: company.or(file.company),
+            license,
+            spdx_headers: spdx_headers || file.spdx_headers.unwrap_or_default(),
+            variables,
+            file_variables,
+            interactive: !no_input && std::io::stdin().is_terminal(),
+        })
+    }
+
+    /// Returns the answer for a template variable, if any
+    ///
+    /// `--var` wins over `GEPETTO_VAR_<NAME>`, which wins over the answers file.
+    ///
+    /// # Arguments
+    /// * `name` - Variable name as declared in the manifest
+    pub fn variable(&self, name: &str) -> Option<String> {
+        self.variables
+            .get(name)
+            .cloned()
+            .or_else(|| {
+                std::env::var(format!("{}{}", VARIABLE_ENV_PREFIX, name.to_uppercase())).ok()
+            })
+            .or_else(|| self.file_variables.get(name).cloned())
+    }
+
+    /// Names of the variables answered with `--var` or the answers file
+    pub fn variable_names(&self) -> impl Iterator<Item = &String> {
+        self.variables.keys().chain(self.file_variables.keys())
+    }
+
+    /// Error for a value that is missing while prompts are disabled
+    ///
+    /// # Arguments
+    /// * `what` - Description of the missing value
+    /// * `hint` - How to provide it
+    pub fn missing(what: &str, hint: &str) -> anyhow::Error {
+        anyhow::anyhow!(
+            "Missing {} and prompts are disabled (--no-input or no terminal), {}",
+            what,
+            hint
+        )
+    }
+}
+
+impl AnswersFile {
+    /// Reads and parses an answers file
+    fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            anyhow::anyhow!("Failed to read answers file '{}': {}", path.display(), e)
+        })?;
+        toml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid answers file '{}': {}", path.display(), e))
+    }
+}
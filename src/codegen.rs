@@ -0,0 +1,1344 @@
+use anyhow::Result;
+use heck::{ToSnakeCase, ToUpperCamelCase};
+use regex::Regex;
+use serde::Serialize;
+use std::str::FromStr;
+use tera::{Context, Tera};
+
+use crate::validation::RUST_KEYWORDS;
+
+/// Handler file of a new instruction
+const INSTRUCTION_TEMPLATE: &str = include_str!("../generators/instruction.rs");
+
+/// Mollusk test appended to `tests/mollusk_tests.rs`
+const INSTRUCTION_TEST_TEMPLATE: &str = include_str!("../generators/instruction_test.rs");
+
+/// Helper building the instruction in `benches/compute_units.rs`
+const INSTRUCTION_BENCH_TEMPLATE: &str = include_str!("../generators/instruction_bench.rs");
+
+/// Source file of a new state account type
+const STATE_TEMPLATE: &str = include_str!("../generators/state.rs");
+
+/// Layout test of the state bytes, appended to `tests/mollusk_tests.rs`
+const STATE_TEST_TEMPLATE: &str = include_str!("../generators/state_test.rs");
+
+/// Instruction argument types and their size in bytes
+const ARG_TYPES: &[(&str, usize)] = &[
+    ("u8", 1),
+    ("u16", 2),
+    ("u32", 4),
+    ("u64", 8),
+    ("u128", 16),
+    ("i8", 1),
+    ("i16", 2),
+    ("i32", 4),
+    ("i64", 8),
+    ("i128", 16),
+    ("bool", 1),
+    ("Pubkey", 32),
+];
+
+/// Flags an account can be given with `--account NAME:FLAGS`
+pub const ACCOUNT_FLAGS: &[&str] = &["signer", "writable", "pda", "program", "system"];
+
+/// Names used by the generated test and benchmark, unavailable to accounts
+const RESERVED_ACCOUNT_NAMES: &[&str] = &[
+    "accounts",
+    "data",
+    "instruction",
+    "ix_accounts",
+    "ix_data",
+    "mollusk",
+    "system_account",
+    "tx_accounts",
+];
+
+/// Names of the generated state methods, locals and header fields,
+/// unavailable to state fields
+const RESERVED_FIELD_NAMES: &[&str] = &[
+    "bump",
+    "data",
+    "key",
+    "load",
+    "load_mut",
+    "seeds",
+    "seeds_with_bump",
+    "set_inner",
+    "signer_seeds_with_bump",
+    "state",
+];
+
+/// Most seeds a PDA can have, leaving room for the bump
+const MAX_SEEDS: usize = 15;
+
+/// Longest seed a PDA can have
+const MAX_SEED_LEN: usize = 32;
+
+/// Checks a name can be used as a Rust identifier in generated code
+///
+/// # Arguments
+/// * `kind` - What the name is for, used in messages
+/// * `name` - The snake_case name
+pub fn validate_identifier(kind: &str, name: &str) -> Result<()> {
+    let valid = name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && name != "_";
+    if !valid {
+        return Err(anyhow::anyhow!(
+            "Invalid {} name '{}', use snake_case letters, digits and underscores",
+            kind,
+            name
+        ));
+    }
+    if RUST_KEYWORDS.contains(&name) {
+        return Err(anyhow::anyhow!(
+            "Invalid {} name '{}', it is a Rust keyword",
+            kind,
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// An account of a generated instruction, e.g. `vault:writable,pda`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountSpec {
+    pub name: String,
+    /// Must sign the transaction
+    pub signer: bool,
+    /// Must be writable
+    pub writable: bool,
+    /// Must be the PDA derived from its seeds
+    pub pda: bool,
+    /// Must be owned by the program
+    pub program: bool,
+    /// Must be the system program, implied by the name `system_program`
+    pub system: bool,
+}
+
+impl AccountSpec {
+    /// Creates an account from its name and flags
+    ///
+    /// # Arguments
+    /// * `name` - snake_case account name
+    /// * `flags` - Any of `ACCOUNT_FLAGS`
+    ///
+    /// # Returns
+    /// The account, or Err for an invalid name or unknown flag
+    pub fn new(name: &str, flags: &[&str]) -> Result<Self> {
+        let name = name.trim().to_snake_case();
+        validate_identifier("account", &name)?;
+        if RESERVED_ACCOUNT_NAMES.contains(&name.as_str()) {
+            return Err(anyhow::anyhow!(
+                "Account name '{}' is used by the generated code, pick another one",
+                name
+            ));
+        }
+        let mut account = AccountSpec {
+            system: name == "system_program",
+            name,
+            ..Default::default()
+        };
+        for flag in flags.iter().map(|flag| flag.trim()) {
+            match flag {
+                "signer" => account.signer = true,
+                "writable" | "mut" => account.writable = true,
+                "pda" => account.pda = true,
+                "program" => account.program = true,
+                "system" => account.system = true,
+                "" => {}
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "Unknown flag '{}' for account '{}', expected: {}",
+                        flag,
+                        account.name,
+                        ACCOUNT_FLAGS.join(", ")
+                    ))
+                }
+            }
+        }
+        if account.system && (account.signer || account.writable || account.pda || account.program)
+        {
+            return Err(anyhow::anyhow!(
+                "Account '{}' is the system program and cannot have other flags",
+                account.name
+            ));
+        }
+        Ok(account)
+    }
+
+    /// Checks run on the account, in the order of the `Create` instruction
+    fn checks(&self) -> Vec<String> {
+        let name = &self.name;
+        let mut checks = Vec::new();
+        if self.signer {
+            checks.push(format!("SignerAccount::check({})?;", name));
+        }
+        if self.writable {
+            checks.push(format!("WritableAccount::check({})?;", name));
+        }
+        if self.program {
+            checks.push(format!("ProgramAccount::check({})?;", name));
+        }
+        if self.pda {
+            checks.push(format!("// Adjust the seeds to how `{}` is derived", name));
+            checks.push(format!(
+                "let (_, {name}_bump) = find_program_address(&[b\"{name}\"], &crate::ID);"
+            ));
+            checks.push(format!(
+                "PdaAccount::check({name}, &[b\"{name}\", &[{name}_bump]])?;"
+            ));
+        }
+        if self.system {
+            checks.push(format!("SystemProgram::check({})?;", name));
+        }
+        checks
+    }
+
+    /// `AccountMeta` of the account in tests and benchmarks
+    fn meta(&self) -> String {
+        let key = if self.system {
+            "system_program"
+        } else {
+            &self.name
+        };
+        match self.writable {
+            true => format!("AccountMeta::new({}, {})", key, self.signer),
+            false => format!("AccountMeta::new_readonly({}, {})", key, self.signer),
+        }
+    }
+}
+
+impl FromStr for AccountSpec {
+    type Err = anyhow::Error;
+
+    /// Parses `NAME[:FLAG,FLAG...]`
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, flags) = s.split_once(':').unwrap_or((s, ""));
+        AccountSpec::new(name, &flags.split(',').collect::<Vec<_>>())
+    }
+}
+
+/// A typed argument of a generated instruction, e.g. `amount:u64`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgSpec {
+    pub name: String,
+    pub ty: &'static str,
+}
+
+impl ArgSpec {
+    /// Size of the argument in the instruction data
+    pub fn size(&self) -> usize {
+        ARG_TYPES
+            .iter()
+            .find(|(ty, _)| *ty == self.ty)
+            .map_or(0, |(_, size)| *size)
+    }
+
+    /// Statement reading the argument from `data` at the given offset
+    fn parse(&self, offset: usize) -> String {
+        let range = format!("{}..{}", offset, offset + self.size());
+        match self.ty {
+            "bool" => format!("let {} = data[{}] != 0;", self.name, offset),
+            "Pubkey" => format!(
+                "let {}: Pubkey = data[{}]\n            .try_into()\n            .map_err(|_| ProgramError::InvalidInstructionData)?;",
+                self.name, range
+            ),
+            ty => format!(
+                "let {} = {}::from_le_bytes(\n            data[{}]\n                .try_into()\n                .map_err(|_| ProgramError::InvalidInstructionData)?,\n        );",
+                self.name, ty, range
+            ),
+        }
+    }
+
+    /// Bytes of a sample value, used by tests and benchmarks
+    fn sample(&self) -> String {
+        match self.ty {
+            "bool" => "[false as u8]".to_string(),
+            "Pubkey" => "Pubkey::new_unique().to_bytes()".to_string(),
+            ty => format!("0{}.to_le_bytes()", ty),
+        }
+    }
+}
+
+impl FromStr for ArgSpec {
+    type Err = anyhow::Error;
+
+    /// Parses `NAME:TYPE`
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, ty) = parse_typed("argument", s)?;
+        if name == "data" {
+            return Err(anyhow::anyhow!(
+                "Argument name 'data' is used by the generated code, pick another one"
+            ));
+        }
+        Ok(ArgSpec { name, ty })
+    }
+}
+
+/// Parses a `NAME:TYPE` pair, with the type one of `ARG_TYPES`
+///
+/// # Arguments
+/// * `kind` - What the pair is for, used in messages
+/// * `s` - The pair, e.g. `amount:u64`
+///
+/// # Returns
+/// The snake_case name and the type
+fn parse_typed(kind: &str, s: &str) -> Result<(String, &'static str)> {
+    let (name, ty) = s
+        .split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("Invalid {} '{}', expected NAME:TYPE", kind, s))?;
+    let name = name.trim().to_snake_case();
+    validate_identifier(kind, &name)?;
+    let ty = ARG_TYPES
+        .iter()
+        .map(|(ty, _)| *ty)
+        .find(|t| t.eq_ignore_ascii_case(ty.trim()))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unknown type '{}' for {} '{}', expected one of: {}",
+                ty.trim(),
+                kind,
+                name,
+                ARG_TYPES
+                    .iter()
+                    .map(|(ty, _)| *ty)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })?;
+    Ok((name, ty))
+}
+
+/// An instruction to generate
+#[derive(Debug, Clone)]
+pub struct InstructionSpec {
+    /// snake_case name, used for the module and functions
+    pub name: String,
+    /// PascalCase name, used for the types
+    pub type_name: String,
+    pub discriminator: u8,
+    pub accounts: Vec<AccountSpec>,
+    pub args: Vec<ArgSpec>,
+}
+
+#[derive(Serialize)]
+struct AccountContext {
+    name: String,
+    system: bool,
+    pda: bool,
+    program: bool,
+    checks: Vec<String>,
+    meta: String,
+}
+
+#[derive(Serialize)]
+struct ArgContext {
+    name: String,
+    ty: &'static str,
+    parse: String,
+    sample: String,
+}
+
+impl InstructionSpec {
+    /// Creates an instruction, checking its accounts and arguments
+    ///
+    /// # Arguments
+    /// * `name` - Instruction name in any case, e.g. `withdraw-fees`
+    /// * `discriminator` - First byte of the instruction data
+    /// * `accounts` - Accounts in the order the instruction expects them
+    /// * `args` - Arguments in the order of the instruction data
+    pub fn new(
+        name: &str,
+        discriminator: u8,
+        accounts: Vec<AccountSpec>,
+        args: Vec<ArgSpec>,
+    ) -> Result<Self> {
+        let snake = name.trim().to_snake_case();
+        validate_identifier("instruction", &snake)?;
+        if accounts.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Instruction '{}' needs at least one account, pass --account NAME[:FLAGS]",
+                snake
+            ));
+        }
+        for (i, account) in accounts.iter().enumerate() {
+            if accounts[..i].iter().any(|a| a.name == account.name) {
+                return Err(anyhow::anyhow!(
+                    "Account '{}' is listed twice",
+                    account.name
+                ));
+            }
+        }
+        if accounts.iter().filter(|a| a.system).count() > 1 {
+            return Err(anyhow::anyhow!("The system program is listed twice"));
+        }
+        for (i, arg) in args.iter().enumerate() {
+            if args[..i].iter().any(|a| a.name == arg.name) {
+                return Err(anyhow::anyhow!("Argument '{}' is listed twice", arg.name));
+            }
+        }
+
+        Ok(InstructionSpec {
+            type_name: snake.to_upper_camel_case(),
+            name: snake,
+            discriminator,
+            accounts,
+            args,
+        })
+    }
+
+    /// Length of the instruction data after the discriminator
+    pub fn data_len(&self) -> usize {
+        self.args.iter().map(ArgSpec::size).sum()
+    }
+
+    fn context(&self) -> Context {
+        let mut context = Context::new();
+        context.insert("name", &self.name);
+        context.insert("type_name", &self.type_name);
+        context.insert("discriminator", &self.discriminator);
+        context.insert("imports", &self.imports());
+        context.insert(
+            "accounts",
+            &self
+                .accounts
+                .iter()
+                .map(|account| AccountContext {
+                    name: account.name.clone(),
+                    system: account.system,
+                    pda: account.pda,
+                    program: account.program,
+                    checks: account.checks(),
+                    meta: account.meta(),
+                })
+                .collect::<Vec<_>>(),
+        );
+        context.insert(
+            "account_names",
+            &self.accounts.iter().map(|a| &a.name).collect::<Vec<_>>(),
+        );
+        let mut offset = 0;
+        let args = self
+            .args
+            .iter()
+            .map(|arg| {
+                let context = ArgContext {
+                    name: arg.name.clone(),
+                    ty: arg.ty,
+                    parse: arg.parse(offset),
+                    sample: arg.sample(),
+                };
+                offset += arg.size();
+                context
+            })
+            .collect::<Vec<_>>();
+        context.insert("args", &args);
+        context.insert(
+            "arg_names",
+            &self.args.iter().map(|a| &a.name).collect::<Vec<_>>(),
+        );
+        context.insert("data_len", &self.data_len());
+        context.insert(
+            "uses_system_program",
+            &self.accounts.iter().any(|a| a.system || !a.program),
+        );
+        context.insert(
+            "uses_system_account",
+            &self.accounts.iter().any(|a| a.system),
+        );
+        context
+    }
+
+    /// `use` declarations of the handler file
+    fn imports(&self) -> String {
+        let mut pinocchio = vec!["account_info::AccountInfo", "program_error::ProgramError"];
+        match (
+            self.accounts.iter().any(|a| a.pda),
+            self.args.iter().any(|a| a.ty == "Pubkey"),
+        ) {
+            (true, true) => pinocchio.push("pubkey::{find_program_address, Pubkey}"),
+            (true, false) => pinocchio.push("pubkey::find_program_address"),
+            (false, true) => pinocchio.push("pubkey::Pubkey"),
+            (false, false) => {}
+        }
+
+        let mut helpers = Vec::new();
+        let any = |f: fn(&AccountSpec) -> bool| self.accounts.iter().any(f);
+        if any(|a| a.signer || a.writable || a.program || a.system) {
+            helpers.push("AccountCheck");
+        }
+        for (used, helper) in [
+            (any(|a| a.pda), "PdaAccount"),
+            (any(|a| a.program), "ProgramAccount"),
+            (any(|a| a.signer), "SignerAccount"),
+            (any(|a| a.system), "SystemProgram"),
+            (any(|a| a.writable), "WritableAccount"),
+        ] {
+            if used {
+                helpers.push(helper);
+            }
+        }
+
+        let mut imports = format!("use pinocchio::{{{}}};", pinocchio.join(", "));
+        match helpers.as_slice() {
+            [] => {}
+            [helper] => imports.push_str(&format!("\n\nuse crate::helpers::{};", helper)),
+            helpers => imports.push_str(&format!(
+                "\n\nuse crate::helpers::{{{}}};",
+                helpers.join(", ")
+            )),
+        }
+        imports
+    }
+
+    /// Renders one of the generator templates
+    fn render(&self, name: &str, template: &str) -> Result<String> {
+        let mut tera = Tera::default();
+        tera.add_raw_template(name, template)?;
+        tera.render(name, &self.context())
+            .map_err(|e| anyhow::anyhow!("Failed to render the {} generator: {}", name, e))
+    }
+
+    /// Source of `instructions/<name>.rs`
+    pub fn handler(&self) -> Result<String> {
+        self.render("instruction.rs", INSTRUCTION_TEMPLATE)
+    }
+
+    /// Mollusk test of the instruction
+    pub fn test(&self) -> Result<String> {
+        self.render("instruction_test.rs", INSTRUCTION_TEST_TEMPLATE)
+    }
+
+    /// Benchmark helper building the instruction and its accounts
+    pub fn bench(&self) -> Result<String> {
+        self.render("instruction_bench.rs", INSTRUCTION_BENCH_TEMPLATE)
+    }
+
+    /// Match arm dispatching the instruction in `entrypoint.rs`
+    ///
+    /// # Arguments
+    /// * `indent` - Indentation of the other arms
+    pub fn entrypoint_arm(&self, indent: &str) -> String {
+        let name = &self.type_name;
+        match self.args.is_empty() {
+            true => format!(
+                "{indent}Some(({name}::DISCRIMINATOR, _)) => {name}::try_from(accounts)?.process(),\n"
+            ),
+            false => format!(
+                "{indent}Some(({name}::DISCRIMINATOR, instruction_data)) => {{\n\
+                 {indent}    {name}::try_from((instruction_data, accounts))?.process()\n\
+                 {indent}}}\n"
+            ),
+        }
+    }
+}
+
+/// Returns the discriminator following the highest one in use
+///
+/// # Arguments
+/// * `sources` - Sources of the existing instruction handlers
+///
+/// # Returns
+/// The next free discriminator, 0 if none is used yet
+pub fn next_discriminator<'a>(sources: impl Iterator<Item = &'a str>) -> Result<u8> {
+    let re = Regex::new(r"DISCRIMINATOR\s*:[^=]*=\s*&\s*(\d+)")?;
+    let highest = sources
+        .flat_map(|source| re.captures_iter(source))
+        .filter_map(|caps| caps[1].parse::<u16>().ok())
+        .max();
+    match highest {
+        None => Ok(0),
+        Some(highest) => u8::try_from(highest + 1)
+            .map_err(|_| anyhow::anyhow!("Every one-byte discriminator is already in use")),
+    }
+}
+
+/// Adds a name to the first `<module>::{...}` import of a source file
+///
+/// # Arguments
+/// * `source` - Rust source importing items of the module
+/// * `module` - Last segment of the module path, e.g. `instructions`
+/// * `item` - Item to import, left alone if it is imported already
+///
+/// # Returns
+/// The updated source, or None if it imports nothing from the module
+pub fn add_import(source: &str, module: &str, item: &str) -> Option<String> {
+    let re = Regex::new(&format!(r"\b{}::(\{{[^}}]*\}}|[A-Za-z_]\w*)", module)).ok()?;
+    let caps = re.captures(source)?;
+    let imported = caps.get(1)?;
+    let mut names = imported
+        .as_str()
+        .trim_matches(['{', '}'])
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect::<Vec<_>>();
+    if names.contains(&item) {
+        return Some(source.to_string());
+    }
+    names.push(item);
+
+    let mut updated = source.to_string();
+    updated.replace_range(imported.range(), &format!("{{{}}}", names.join(", ")));
+    Some(updated)
+}
+
+/// Declares and re-exports a module in a `mod.rs`
+///
+/// A declaration or re-export that is already there is not repeated.
+///
+/// # Arguments
+/// * `source` - Contents of the `mod.rs`
+/// * `module` - Module name
+pub fn add_module(source: &str, module: &str) -> String {
+    let mut lines = source.lines().map(str::to_string).collect::<Vec<_>>();
+    let insert_after = |lines: &mut Vec<String>, prefix: &str, line: String| {
+        if lines.iter().any(|l| l.trim() == line) {
+            return;
+        }
+        match lines.iter().rposition(|l| l.starts_with(prefix)) {
+            Some(i) => lines.insert(i + 1, line),
+            None => {
+                if lines.last().is_some_and(|l| !l.is_empty()) {
+                    lines.push(String::new());
+                }
+                lines.push(line)
+            }
+        }
+    };
+    insert_after(&mut lines, "pub mod ", format!("pub mod {};", module));
+    insert_after(&mut lines, "pub use ", format!("pub use {}::*;", module));
+    lines.join("\n") + "\n"
+}
+
+/// Inserts the instruction's match arm before the catch-all arm
+///
+/// # Arguments
+/// * `source` - Contents of `entrypoint.rs`
+/// * `spec` - The instruction to dispatch
+///
+/// # Returns
+/// The updated source, or None if it has no `_ =>` arm
+pub fn add_entrypoint_arm(source: &str, spec: &InstructionSpec) -> Option<String> {
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        if line.trim_start().starts_with("_ =>") {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            let mut updated = source.to_string();
+            updated.insert_str(offset, &spec.entrypoint_arm(indent));
+            return Some(updated);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Adds the benchmark helper and its run to `benches/compute_units.rs`
+///
+/// The helper goes above `fn main()`, its instruction is built before the
+/// bencher runs and benchmarked last.
+///
+/// # Arguments
+/// * `source` - Contents of `benches/compute_units.rs`
+/// * `spec` - The instruction to benchmark
+/// * `helper` - The helper rendered by `InstructionSpec::bench`, possibly formatted
+///
+/// # Returns
+/// The updated source, or None if the bench has no such layout
+pub fn add_bench(source: &str, spec: &InstructionSpec, helper: &str) -> Option<String> {
+    let name = &spec.name;
+    let (Some(main), Some(bencher), Some(must_pass)) = (
+        source.find("\nfn main()"),
+        source.find("MolluskComputeUnitBencher::new("),
+        source.find(".must_pass("),
+    ) else {
+        return None;
+    };
+    let line_start = |position: usize| source[..position].rfind('\n').map_or(0, |i| i + 1);
+    let bencher = match source[..bencher].rfind("// Run benchmarks") {
+        Some(comment) => line_start(comment),
+        None => line_start(bencher),
+    };
+    let must_pass = line_start(must_pass);
+
+    let mut updated = source.to_string();
+    // Insert from the end so earlier positions stay valid
+    updated.insert_str(
+        must_pass,
+        &format!(
+            "        .bench((\"{}\", &{}_instruction, &{}_accounts))\n",
+            name, name, name
+        ),
+    );
+    updated.insert_str(
+        bencher,
+        &format!("    let ({name}_instruction, {name}_accounts) = build_{name}_instruction();\n\n"),
+    );
+    updated.insert_str(main + 1, &format!("{}\n\n", helper.trim_end()));
+    Some(updated)
+}
+
+/// A field of a generated state account type, e.g. `amount:u64`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSpec {
+    pub name: String,
+    pub ty: &'static str,
+}
+
+impl FieldSpec {
+    /// Type the field is stored as
+    ///
+    /// Multi-byte integers are stored as little-endian byte arrays and bools
+    /// as `u8`, so the struct has an alignment of 1 and no padding.
+    fn storage(&self) -> String {
+        match self.ty {
+            "bool" => "u8".to_string(),
+            "u8" | "i8" | "Pubkey" => self.ty.to_string(),
+            _ => format!("[u8; {}]", self.size()),
+        }
+    }
+
+    /// Size of the field in the account data
+    fn size(&self) -> usize {
+        ARG_TYPES
+            .iter()
+            .find(|(ty, _)| *ty == self.ty)
+            .map_or(0, |(_, size)| *size)
+    }
+
+    /// Whether the field is read and written through accessors
+    fn has_accessors(&self) -> bool {
+        self.storage() != self.ty
+    }
+
+    /// Expression reading the field in its own type
+    fn getter(&self) -> String {
+        match self.ty {
+            "bool" => format!("self.{} != 0", self.name),
+            ty => format!("{}::from_le_bytes(self.{})", ty, self.name),
+        }
+    }
+
+    /// Statement writing the field from a value of its own type
+    fn setter(&self) -> String {
+        match self.ty {
+            "bool" => format!("self.{0} = {0} as u8;", self.name),
+            _ => format!("self.{0} = {0}.to_le_bytes();", self.name),
+        }
+    }
+
+    /// Sample value of the field, used by the layout test
+    fn sample(&self, index: usize) -> String {
+        match self.ty {
+            "bool" => "true".to_string(),
+            "Pubkey" => "Pubkey::new_unique().to_bytes()".to_string(),
+            ty => format!("{}{}", index + 1, ty),
+        }
+    }
+
+    /// Expression of the bytes the field is stored as, given its value
+    fn bytes(&self) -> String {
+        match self.ty {
+            "bool" => format!("[{} as u8]", self.name),
+            "Pubkey" => self.name.clone(),
+            _ => format!("{}.to_le_bytes()", self.name),
+        }
+    }
+}
+
+impl FromStr for FieldSpec {
+    type Err = anyhow::Error;
+
+    /// Parses `NAME:TYPE`
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, ty) = parse_typed("field", s)?;
+        if RESERVED_FIELD_NAMES.contains(&name.as_str()) {
+            return Err(anyhow::anyhow!(
+                "Field name '{}' is used by the generated code, pick another one",
+                name
+            ));
+        }
+        Ok(FieldSpec { name, ty })
+    }
+}
+
+/// A seed of a state PDA, e.g. `counter` or `authority:Pubkey`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeedSpec {
+    /// Constant bytes, e.g. `b"counter"`
+    Literal(String),
+    /// Parameter of the seed functions, e.g. `authority: &Pubkey`
+    Param { name: String, ty: &'static str },
+}
+
+impl SeedSpec {
+    /// Expression of the seed as `&[u8]`
+    fn item(&self) -> String {
+        match self {
+            SeedSpec::Literal(literal) => format!("b\"{}\"", literal),
+            SeedSpec::Param { name, .. } => format!("{}.as_ref()", name),
+        }
+    }
+
+    /// Referenced type of the parameter, integers being passed as their bytes
+    fn param_type(&self) -> Option<String> {
+        match self {
+            SeedSpec::Literal(_) => None,
+            SeedSpec::Param { ty: "Pubkey", .. } => Some("Pubkey".to_string()),
+            SeedSpec::Param { ty, .. } => ARG_TYPES
+                .iter()
+                .find(|(t, _)| t == ty)
+                .map(|(_, size)| format!("[u8; {}]", size)),
+        }
+    }
+}
+
+impl FromStr for SeedSpec {
+    type Err = anyhow::Error;
+
+    /// Parses a literal seed, or `NAME:TYPE` for a parameter
+    fn from_str(s: &str) -> Result<Self> {
+        if s.contains(':') {
+            let (name, ty) = parse_typed("seed", s)?;
+            if matches!(name.as_str(), "bump" | "seeds") {
+                return Err(anyhow::anyhow!(
+                    "Seed name '{}' is used by the generated code, pick another one",
+                    name
+                ));
+            }
+            if ty == "bool" {
+                return Err(anyhow::anyhow!(
+                    "Seed '{}' cannot be a bool, use u8 instead",
+                    name
+                ));
+            }
+            return Ok(SeedSpec::Param { name, ty });
+        }
+
+        let literal = s.trim();
+        if literal.is_empty()
+            || literal.len() > MAX_SEED_LEN
+            || !literal
+                .chars()
+                .all(|c| c.is_ascii_graphic() && c != '"' && c != '\\')
+        {
+            return Err(anyhow::anyhow!(
+                "Invalid seed '{}', use up to {} printable ASCII characters, or NAME:TYPE for a parameter",
+                literal,
+                MAX_SEED_LEN
+            ));
+        }
+        Ok(SeedSpec::Literal(literal.to_string()))
+    }
+}
+
+/// A state account type to generate
+#[derive(Debug, Clone)]
+pub struct StateSpec {
+    /// snake_case name, used for the module
+    pub name: String,
+    /// PascalCase name, used for the type and its `StateKey` variant
+    pub type_name: String,
+    pub fields: Vec<FieldSpec>,
+    /// Seeds of the PDA holding the state, empty if it is not a PDA
+    pub seeds: Vec<SeedSpec>,
+}
+
+#[derive(Serialize)]
+struct FieldContext {
+    name: String,
+    ty: &'static str,
+    storage: String,
+    accessors: bool,
+    getter: String,
+    setter: String,
+    sample: String,
+    /// Byte range of the field in the account data
+    start: usize,
+    end: usize,
+    bytes: String,
+}
+
+impl StateSpec {
+    /// Creates a state type, checking its fields and seeds
+    ///
+    /// # Arguments
+    /// * `name` - State name in any case, e.g. `vault-config`
+    /// * `fields` - Fields after the key and bump, in layout order
+    /// * `seeds` - Seeds of the PDA, in order
+    pub fn new(name: &str, fields: Vec<FieldSpec>, seeds: Vec<SeedSpec>) -> Result<Self> {
+        let snake = name.trim().to_snake_case();
+        validate_identifier("state", &snake)?;
+        if fields.is_empty() {
+            return Err(anyhow::anyhow!(
+                "State '{}' needs at least one field, pass --field NAME:TYPE",
+                snake
+            ));
+        }
+        for (i, field) in fields.iter().enumerate() {
+            if fields[..i].iter().any(|f| f.name == field.name) {
+                return Err(anyhow::anyhow!("Field '{}' is listed twice", field.name));
+            }
+        }
+        if seeds.len() > MAX_SEEDS {
+            return Err(anyhow::anyhow!(
+                "A PDA has at most {} seeds besides its bump, {} given",
+                MAX_SEEDS,
+                seeds.len()
+            ));
+        }
+        let params = seeds.iter().filter_map(|seed| match seed {
+            SeedSpec::Param { name, .. } => Some(name),
+            SeedSpec::Literal(_) => None,
+        });
+        for (i, name) in params.clone().enumerate() {
+            if params.clone().take(i).any(|other| other == name) {
+                return Err(anyhow::anyhow!("Seed '{}' is listed twice", name));
+            }
+        }
+
+        Ok(StateSpec {
+            type_name: snake.to_upper_camel_case(),
+            name: snake,
+            fields,
+            seeds,
+        })
+    }
+
+    fn context(&self) -> Context {
+        let mut context = Context::new();
+        context.insert("name", &self.name);
+        context.insert("type_name", &self.type_name);
+        context.insert("imports", &self.imports());
+
+        // The key and the bump take one byte each
+        let mut offset = if self.seeds.is_empty() { 1 } else { 2 };
+        let mut fields = Vec::new();
+        for (i, field) in self.fields.iter().enumerate() {
+            fields.push(FieldContext {
+                name: field.name.clone(),
+                ty: field.ty,
+                storage: field.storage(),
+                accessors: field.has_accessors(),
+                getter: field.getter(),
+                setter: field.setter(),
+                sample: field.sample(i),
+                start: offset,
+                end: offset + field.size(),
+                bytes: field.bytes(),
+            });
+            offset += field.size();
+        }
+        context.insert("fields", &fields);
+        context.insert("len", &offset);
+        let mut params = self.fields.iter().map(|f| &f.name).collect::<Vec<_>>();
+        let bump = "bump".to_string();
+        if !self.seeds.is_empty() {
+            params.push(&bump);
+        }
+        context.insert("set_inner_params", &params);
+        context.insert("pda", &!self.seeds.is_empty());
+        if !self.seeds.is_empty() {
+            self.insert_seeds(&mut context);
+        }
+        context
+    }
+
+    /// Signatures and bodies of `seeds`, `seeds_with_bump` and
+    /// `signer_seeds_with_bump`
+    ///
+    /// Lifetimes are only spelled out where elision cannot infer them.
+    fn insert_seeds(&self, context: &mut Context) {
+        let params = self
+            .seeds
+            .iter()
+            .filter_map(|seed| match seed {
+                SeedSpec::Param { name, .. } => Some((name.as_str(), seed.param_type()?)),
+                SeedSpec::Literal(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let signature = |params: &[(&str, String)], returns: &str, seed: &str| {
+            let (generics, lifetime) = match params.len() {
+                0 => ("", "'static "),
+                1 => ("", ""),
+                _ => ("<'a>", "'a "),
+            };
+            let params = params
+                .iter()
+                .map(|(name, ty)| format!("{}: &{}{}", name, lifetime, ty))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let seed = match (seed, lifetime) {
+                ("Seed", "") => "Seed<'_>".to_string(),
+                ("Seed", _) => "Seed<'a>".to_string(),
+                (_, lifetime) => format!("&{}[u8]", lifetime),
+            };
+            format!("{}({}) -> [{}; {}]", generics, params, seed, returns)
+        };
+
+        let len = self.seeds.len();
+        let mut with_bump = params.clone();
+        with_bump.push(("bump", "[u8]".to_string()));
+        let names = params.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        let mut names_with_bump = names.clone();
+        names_with_bump.push("bump");
+
+        context.insert(
+            "seeds_signature",
+            &signature(&params, &len.to_string(), "&[u8]"),
+        );
+        context.insert(
+            "with_bump_signature",
+            &signature(&with_bump, &(len + 1).to_string(), "&[u8]"),
+        );
+        context.insert(
+            "signer_signature",
+            &signature(&with_bump, &(len + 1).to_string(), "Seed"),
+        );
+        context.insert(
+            "seed_items",
+            &self.seeds.iter().map(SeedSpec::item).collect::<Vec<_>>(),
+        );
+        context.insert("seed_args", &names.join(", "));
+        context.insert("seed_args_with_bump", &names_with_bump.join(", "));
+        context.insert("seed_count", &len);
+    }
+
+    /// `use` declarations of the state file
+    fn imports(&self) -> String {
+        let mut pinocchio = Vec::new();
+        if !self.seeds.is_empty() {
+            pinocchio.push("instruction::Seed");
+        }
+        pinocchio.push("program_error::ProgramError");
+        if self.fields.iter().any(|f| f.ty == "Pubkey")
+            || self
+                .seeds
+                .iter()
+                .any(|s| s.param_type().as_deref() == Some("Pubkey"))
+        {
+            pinocchio.push("pubkey::Pubkey");
+        }
+        let pinocchio = match pinocchio.as_slice() {
+            [item] => item.to_string(),
+            items => format!("{{{}}}", items.join(", ")),
+        };
+        format!(
+            "use core::mem::size_of;\nuse pinocchio::{};\n\nuse super::StateKey;",
+            pinocchio
+        )
+    }
+
+    /// Renders one of the generator templates
+    fn render(&self, name: &str, template: &str) -> Result<String> {
+        let mut tera = Tera::default();
+        tera.add_raw_template(name, template)?;
+        tera.render(name, &self.context())
+            .map_err(|e| anyhow::anyhow!("Failed to render the {} generator: {}", name, e))
+    }
+
+    /// Source of `state/<name>.rs`
+    pub fn source(&self) -> Result<String> {
+        self.render("state.rs", STATE_TEMPLATE)
+    }
+
+    /// Test writing the state into account data and checking its layout
+    pub fn test(&self) -> Result<String> {
+        self.render("state_test.rs", STATE_TEST_TEMPLATE)
+    }
+}
+
+/// Adds the state's variant to the `StateKey` enum, after the last one
+///
+/// # Arguments
+/// * `source` - Rust source defining `enum StateKey`
+/// * `type_name` - Name of the variant
+///
+/// # Returns
+/// The updated source and the variant's value, or None if the source does
+/// not define the enum
+pub fn add_state_key(source: &str, type_name: &str) -> Result<Option<(String, u8)>> {
+    let Some(start) = Regex::new(r"\benum\s+StateKey\s*\{")?.find(source) else {
+        return Ok(None);
+    };
+    let Some(len) = source[start.end()..].find('}') else {
+        return Ok(None);
+    };
+    let body = start.end()..start.end() + len;
+
+    let variant_re = Regex::new(r"(?m)^[ \t]*(\w+)[ \t]*=[ \t]*(\d+)(?:[ \t]*,)?")?;
+    let mut last_end = None;
+    let mut highest = None;
+    for caps in variant_re.captures_iter(&source[body.clone()]) {
+        if &caps[1] == type_name {
+            return Err(anyhow::anyhow!(
+                "StateKey already has a '{}' variant",
+                type_name
+            ));
+        }
+        let value = caps[2].parse::<u16>()?;
+        highest = highest.max(Some(value));
+        last_end = caps.get(0).map(|m| body.start + m.end());
+    }
+    let key = match highest {
+        None => 0,
+        Some(highest) => u8::try_from(highest + 1)
+            .map_err(|_| anyhow::anyhow!("Every StateKey value is already in use"))?,
+    };
+
+    let mut updated = source.to_string();
+    match last_end {
+        Some(end) => {
+            let line_start = source[..end].rfind('\n').map_or(0, |i| i + 1);
+            let line = &source[line_start..end];
+            let indent = &line[..line.len() - line.trim_start().len()];
+            // After a trailing comment, if any, on the last variant's line
+            let line_end = source[end..body.end]
+                .find('\n')
+                .map_or(body.end, |i| end + i);
+            updated.insert_str(line_end, &format!("\n{}{} = {},", indent, type_name, key));
+            if !line.ends_with(',') {
+                updated.insert(end, ',');
+            }
+        }
+        None => {
+            let close = if source[body.clone()].contains('\n') {
+                ""
+            } else {
+                "\n"
+            };
+            updated.insert_str(
+                body.start,
+                &format!("\n    {} = {},{}", type_name, key, close),
+            );
+        }
+    }
+    Ok(Some((updated, key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn withdraw(args: Vec<ArgSpec>) -> InstructionSpec {
+        let accounts = vec![AccountSpec::new("vault", &["writable"]).unwrap()];
+        InstructionSpec::new("withdraw", 2, accounts, args).unwrap()
+    }
+
+    #[test]
+    fn test_validate_identifier() {
+        for name in ["amount", "_vault", "fee2"] {
+            assert!(validate_identifier("arg", name).is_ok(), "'{}'", name);
+        }
+        // Strict and reserved keywords alike
+        for name in [
+            "try", "yield", "gen", "fn", "self", "", "2x", "_", "Amount", "my-arg",
+        ] {
+            assert!(validate_identifier("arg", name).is_err(), "'{}'", name);
+        }
+    }
+
+    #[test]
+    fn test_add_import() {
+        let source = "use crate::instructions::{Create, Increase};\n";
+        assert_eq!(
+            add_import(source, "instructions", "Withdraw").as_deref(),
+            Some("use crate::instructions::{Create, Increase, Withdraw};\n")
+        );
+
+        // A single import becomes a list
+        let source = "use crate::instructions::Create;\n";
+        assert_eq!(
+            add_import(source, "instructions", "Withdraw").as_deref(),
+            Some("use crate::instructions::{Create, Withdraw};\n")
+        );
+
+        // Nested in a larger import, only the module's list changes
+        let source =
+            "use counter::{\n    instructions::{Create},\n    state::{Counter},\n    ID,\n};\n";
+        assert_eq!(
+            add_import(source, "state", "Vault").as_deref(),
+            Some("use counter::{\n    instructions::{Create},\n    state::{Counter, Vault},\n    ID,\n};\n")
+        );
+
+        // An item imported already is not repeated
+        let source = "use crate::state::{to_bytes, StateKey};\n";
+        assert_eq!(
+            add_import(source, "state", "StateKey").as_deref(),
+            Some(source)
+        );
+
+        // `my_state::` is a different module
+        assert_eq!(add_import("use my_state::Foo;\n", "state", "Vault"), None);
+        assert_eq!(
+            add_import("fn main() {}\n", "instructions", "Withdraw"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_add_module() {
+        let source =
+            "pub mod create;\npub mod increase;\n\npub use create::*;\npub use increase::*;\n";
+        assert_eq!(
+            add_module(source, "withdraw"),
+            "pub mod create;\npub mod increase;\npub mod withdraw;\n\npub use create::*;\npub use increase::*;\npub use withdraw::*;\n"
+        );
+
+        // Already declared and re-exported: unchanged
+        assert_eq!(add_module(source, "increase"), source);
+
+        // Declared but not re-exported: only the re-export is added
+        let source = "pub mod create;\n";
+        assert_eq!(
+            add_module(source, "create"),
+            "pub mod create;\n\npub use create::*;\n"
+        );
+
+        assert_eq!(
+            add_module("", "create"),
+            "pub mod create;\n\npub use create::*;\n"
+        );
+    }
+
+    #[test]
+    fn test_add_entrypoint_arm() {
+        let source = "\
+    match instruction_data.split_first() {
+        Some((Create::DISCRIMINATOR, _)) => Create::try_from(accounts)?.process(), // create
+        _ => Err(ProgramError::InvalidInstructionData), // unknown instruction
+    }
+";
+        let expected = "\
+    match instruction_data.split_first() {
+        Some((Create::DISCRIMINATOR, _)) => Create::try_from(accounts)?.process(), // create
+        Some((Withdraw::DISCRIMINATOR, _)) => Withdraw::try_from(accounts)?.process(),
+        _ => Err(ProgramError::InvalidInstructionData), // unknown instruction
+    }
+";
+        assert_eq!(
+            add_entrypoint_arm(source, &withdraw(Vec::new())).as_deref(),
+            Some(expected)
+        );
+
+        let amount = "amount:u64".parse().unwrap();
+        let updated = add_entrypoint_arm(source, &withdraw(vec![amount])).unwrap();
+        assert!(updated.contains(
+            "        Some((Withdraw::DISCRIMINATOR, instruction_data)) => {\n            \
+             Withdraw::try_from((instruction_data, accounts))?.process()\n        }\n        _ =>"
+        ));
+
+        // Without a catch-all arm there is nowhere to dispatch from
+        let source = "match x {\n    Some(_) => Ok(()),\n    None => Ok(()),\n}\n";
+        assert_eq!(add_entrypoint_arm(source, &withdraw(Vec::new())), None);
+    }
+
+    #[test]
+    fn test_add_bench() {
+        let source = "\
+fn helper() {}
+
+fn main() {
+    let mollusk = mollusk();
+
+    // Run benchmarks
+    MolluskComputeUnitBencher::new(mollusk)
+        .bench((\"create\", &create_instruction, &create_accounts))
+        .must_pass(true)
+        .execute();
+}
+";
+        let expected = "\
+fn helper() {}
+
+fn build_withdraw_instruction() {}
+
+fn main() {
+    let mollusk = mollusk();
+
+    let (withdraw_instruction, withdraw_accounts) = build_withdraw_instruction();
+
+    // Run benchmarks
+    MolluskComputeUnitBencher::new(mollusk)
+        .bench((\"create\", &create_instruction, &create_accounts))
+        .bench((\"withdraw\", &withdraw_instruction, &withdraw_accounts))
+        .must_pass(true)
+        .execute();
+}
+";
+        let spec = withdraw(Vec::new());
+        let helper = "fn build_withdraw_instruction() {}\n";
+        assert_eq!(add_bench(source, &spec, helper).as_deref(), Some(expected));
+
+        assert_eq!(add_bench("fn main() {}\n", &spec, helper), None);
+    }
+
+    #[test]
+    fn test_next_discriminator() {
+        assert_eq!(next_discriminator(std::iter::empty()).unwrap(), 0);
+
+        // Gaps are not reused, the next one follows the highest
+        let sources = [
+            "pub const DISCRIMINATOR: &'a u8 = &0;",
+            "pub const DISCRIMINATOR: &'a u8 = &5;",
+            "pub const DISCRIMINATOR : &u8 = & 2 ;",
+            "// no instruction here",
+        ];
+        assert_eq!(next_discriminator(sources.into_iter()).unwrap(), 6);
+
+        let full = ["pub const DISCRIMINATOR: &'a u8 = &255;"];
+        assert!(next_discriminator(full.into_iter()).is_err());
+    }
+
+    #[test]
+    fn test_add_state_key() {
+        let source =
+            "#[repr(u8)]\npub enum StateKey {\n    Uninitialized = 0,\n    Counter = 1,\n}\n";
+        assert_eq!(
+            add_state_key(source, "Vault").unwrap(),
+            Some((
+                "#[repr(u8)]\npub enum StateKey {\n    Uninitialized = 0,\n    Counter = 1,\n    Vault = 2,\n}\n"
+                    .to_string(),
+                2
+            ))
+        );
+
+        // Gaps are not reused, and a missing trailing comma is added
+        let source = "enum StateKey {\n    A = 0,\n    B = 7,\n    C = 3\n}\n";
+        let (updated, key) = add_state_key(source, "Vault").unwrap().unwrap();
+        assert_eq!(key, 8);
+        assert_eq!(
+            updated,
+            "enum StateKey {\n    A = 0,\n    B = 7,\n    C = 3,\n    Vault = 8,\n}\n"
+        );
+
+        // A trailing comment stays on its variant's line
+        let source = "enum StateKey {\n    A = 0, // first\n    B = 1 // second\n}\n";
+        let (updated, _) = add_state_key(source, "Vault").unwrap().unwrap();
+        assert_eq!(
+            updated,
+            "enum StateKey {\n    A = 0, // first\n    B = 1, // second\n    Vault = 2,\n}\n"
+        );
+
+        let (updated, key) = add_state_key("enum StateKey {}\n", "Vault")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (updated.as_str(), key),
+            ("enum StateKey {\n    Vault = 0,\n}\n", 0)
+        );
+
+        assert!(add_state_key(source, "B").is_err());
+        assert!(add_state_key("enum StateKey {\n    Full = 255,\n}\n", "Vault").is_err());
+    }
+
+    #[test]
+    fn test_add_state_key_without_enum() {
+        let source = "pub enum AccountKey {\n    Counter = 0,\n}\n";
+        assert_eq!(add_state_key(source, "Vault").unwrap(), None);
+        assert_eq!(add_state_key("", "Vault").unwrap(), None);
+    }
+}
@@ -0,0 +1,1089 @@
+use anyhow::Result;
+use console::style;
+use dialoguer::{Input, MultiSelect, Select};
+use heck::ToSnakeCase;
+use regex::Regex;
+use solana_sdk::signature::Signer;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::answers::Answers;
+use crate::codegen::{
+    add_bench, add_entrypoint_arm, add_import, add_module, add_state_key, next_discriminator,
+    AccountSpec, ArgSpec, FieldSpec, InstructionSpec, SeedSpec, StateSpec, ACCOUNT_FLAGS,
+};
+use crate::commands::new::{load_template, print_template_source};
+use crate::config::{KeypairOptions, ProjectConfig};
+use crate::keypair::{
+    keystore_dir, read_keypair_file, store_keypairs, write_keypair_file, DEPLOYER_FILE,
+    PROGRAM_ID_FILE,
+};
+use crate::license::License;
+use crate::staging::StagingDir;
+use crate::template::{render_partial, render_project_files, write_rendered_file, FileAction};
+use crate::user_config::UserConfig;
+use crate::validation::validate_program_name;
+
+/// Directory of the workspace holding one crate per program
+const PROGRAMS_DIR: &str = "programs";
+
+/// Line of the README below which `add program` inserts deploy commands
+const README_DEPLOY_MARKER: &str = "<!-- gepetto:deploy -->";
+
+/// Partial rendering the deploy command of a program
+const DEPLOY_PARTIAL: &str = "deploy.md";
+
+/// Options of the `add program` command
+#[derive(Debug, Default)]
+pub struct AddProgramOptions {
+    /// Answers given with flags, env vars or an answers file
+    pub answers: Answers,
+    /// Custom template directory, uses the built-in template if None
+    pub template_path: Option<PathBuf>,
+    /// Directory inside the workspace, defaults to the current directory
+    pub path: Option<PathBuf>,
+}
+
+/// A program crate of the workspace
+#[derive(Debug)]
+struct WorkspaceProgram {
+    /// Directory of the crate
+    dir: PathBuf,
+    /// `[package]` table of its `Cargo.toml`
+    package: toml::Table,
+}
+
+/// Finds the Cargo workspace containing a directory
+///
+/// # Arguments
+/// * `start` - Directory to search from, upwards
+///
+/// # Returns
+/// The directory of the workspace `Cargo.toml`
+pub(crate) fn find_workspace_root(start: &Path) -> Result<PathBuf> {
+    let start = fs::canonicalize(start)
+        .map_err(|e| anyhow::anyhow!("Cannot access '{}': {}", start.display(), e))?;
+    for dir in start.ancestors() {
+        let manifest = dir.join("Cargo.toml");
+        if !manifest.is_file() {
+            continue;
+        }
+        let content = fs::read_to_string(&manifest)?;
+        let table = toml::from_str::<toml::Table>(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid '{}': {}", manifest.display(), e))?;
+        if table.contains_key("workspace") {
+            return Ok(dir.to_path_buf());
+        }
+    }
+    Err(anyhow::anyhow!(
+        "No Cargo workspace found in '{}' or its parents, run this inside a project created by `gepetto new`",
+        start.display()
+    ))
+}
+
+/// Lists the program crates below `programs/`
+fn workspace_programs(root: &Path) -> Result<Vec<WorkspaceProgram>> {
+    let programs_dir = root.join(PROGRAMS_DIR);
+    if !programs_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut programs = Vec::new();
+    for entry in fs::read_dir(&programs_dir)? {
+        let dir = entry?.path();
+        let manifest = dir.join("Cargo.toml");
+        if !manifest.is_file() {
+            continue;
+        }
+        let table = toml::from_str::<toml::Table>(&fs::read_to_string(&manifest)?)
+            .map_err(|e| anyhow::anyhow!("Invalid '{}': {}", manifest.display(), e))?;
+        if let Some(toml::Value::Table(package)) = table.get("package") {
+            programs.push(WorkspaceProgram {
+                dir,
+                package: package.clone(),
+            });
+        }
+    }
+    programs.sort_by(|a, b| a.dir.cmp(&b.dir));
+    Ok(programs)
+}
+
+/// Checks a program can be added without touching an existing crate
+///
+/// # Arguments
+/// * `root` - Workspace root
+/// * `programs` - Program crates already in the workspace
+/// * `name` - Dash-separated name of the new program
+fn validate_new_program(root: &Path, programs: &[WorkspaceProgram], name: &str) -> Result<()> {
+    validate_program_name(name)?;
+    let program_dir = root.join(PROGRAMS_DIR).join(name);
+    if program_dir.exists() {
+        return Err(anyhow::anyhow!(
+            "'{}' already exists, pick another program name",
+            program_dir.display()
+        ));
+    }
+    let crate_name = name.replace('-', "_");
+    if let Some(existing) = programs.iter().find(|program| {
+        program
+            .package
+            .get("name")
+            .and_then(|n| n.as_str())
+            .is_some_and(|n| n.replace('-', "_") == crate_name)
+    }) {
+        return Err(anyhow::anyhow!(
+            "Crate '{}' already exists in '{}', pick another program name",
+            crate_name,
+            existing.dir.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Answers the license questions like the programs already in the workspace
+///
+/// The license comes from the first program's `Cargo.toml`, and SPDX headers
+/// are added when its `lib.rs` has one. Explicit answers take precedence.
+fn inherit_license(answers: &mut Answers, programs: &[WorkspaceProgram]) {
+    let Some(program) = programs.first() else {
+        return;
+    };
+    if answers.license.is_none() {
+        answers.license = program
+            .package
+            .get("license")
+            .and_then(|license| license.as_str())
+            .and_then(|license| license.parse::<License>().ok());
+    }
+    if !answers.spdx_headers {
+        answers.spdx_headers = fs::read_to_string(program.dir.join("src").join("lib.rs"))
+            .is_ok_and(|lib| lib.starts_with("// SPDX-License-Identifier:"));
+    }
+}
+
+/// Returns true if the workspace members include the program directory
+///
+/// Only exact paths and the `programs/*` glob are recognized.
+fn is_workspace_member(root: &Path, program_dir: &Path) -> Result<bool> {
+    let manifest = fs::read_to_string(root.join("Cargo.toml"))?;
+    let table = toml::from_str::<toml::Table>(&manifest)?;
+    let members = table
+        .get("workspace")
+        .and_then(|workspace| workspace.get("members"))
+        .and_then(|members| members.as_array())
+        .map(|members| {
+            members
+                .iter()
+                .filter_map(|m| m.as_str())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let program_dir = program_dir.to_string_lossy().replace('\\', "/");
+    let glob = format!("{}/*", PROGRAMS_DIR);
+    Ok(members
+        .iter()
+        .any(|member| member.trim_end_matches('/') == program_dir || *member == glob))
+}
+
+/// Adds a program to the workflow's `program` matrix
+///
+/// # Arguments
+/// * `workflow` - Contents of a GitHub Actions workflow
+/// * `name` - Directory name of the program below `programs/`
+///
+/// # Returns
+/// The updated workflow, or None if it has no `program` matrix
+fn extend_program_matrix(workflow: &str, name: &str) -> Option<String> {
+    let mut lines = workflow.lines().collect::<Vec<_>>();
+    let start = lines.iter().position(|line| line.trim() == "program:")?;
+    let items = lines[start + 1..]
+        .iter()
+        .take_while(|line| line.trim_start().starts_with("- "))
+        .count();
+    let last = *lines.get(start + items).filter(|_| items > 0)?;
+    let indent = &last[..last.len() - last.trim_start().len()];
+    let item = format!("{}- {}", indent, name);
+    lines.insert(start + items + 1, &item);
+
+    let mut extended = lines.join("\n");
+    if workflow.ends_with('\n') {
+        extended.push('\n');
+    }
+    Some(extended)
+}
+
+/// Adds the program to the CI matrix of every workflow that has one
+///
+/// # Returns
+/// The workflows that were updated, relative to the workspace root
+fn extend_workflows(root: &Path, name: &str) -> Result<Vec<PathBuf>> {
+    let workflows_dir = Path::new(".github").join("workflows");
+    if !root.join(&workflows_dir).is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut updated = Vec::new();
+    for entry in fs::read_dir(root.join(&workflows_dir))? {
+        let path = entry?.path();
+        if !path
+            .extension()
+            .is_some_and(|ext| ext == "yml" || ext == "yaml")
+        {
+            continue;
+        }
+        let content = fs::read_to_string(&path)?;
+        if let Some(extended) = extend_program_matrix(&content, name) {
+            fs::write(&path, extended)
+                .map_err(|e| anyhow::anyhow!("Failed to write '{}': {}", path.display(), e))?;
+            updated.push(workflows_dir.join(path.file_name().unwrap_or_default()));
+        }
+    }
+    updated.sort();
+    Ok(updated)
+}
+
+/// Inserts the program's deploy command above the README marker
+///
+/// # Returns
+/// True if the README was updated
+fn extend_readme(root: &Path, config: &ProjectConfig, deploy: &str) -> Result<bool> {
+    let path = root.join("README.md");
+    let Ok(readme) = fs::read_to_string(&path) else {
+        return Ok(false);
+    };
+    let Some(extended) = add_readme_section(&readme, &config.program_name_readable, deploy) else {
+        return Ok(false);
+    };
+    fs::write(&path, extended)
+        .map_err(|e| anyhow::anyhow!("Failed to write '{}': {}", path.display(), e))?;
+    Ok(true)
+}
+
+/// Inserts a titled deploy section above the README marker
+///
+/// # Returns
+/// The updated README, or None if it has no marker
+fn add_readme_section(readme: &str, title: &str, deploy: &str) -> Option<String> {
+    let position = readme.find(README_DEPLOY_MARKER)?;
+    let mut extended = readme.to_string();
+    extended.insert_str(
+        position,
+        &format!("{}:\n\n{}\n\n", title, deploy.trim_end()),
+    );
+    Some(extended)
+}
+
+/// Adds a program crate to an existing workspace
+///
+/// Renders the program portion of the template, everything that lands in
+/// `programs/<name>/`, with a fresh program keypair, then adds the program to
+/// the CI matrix and the README deploy commands. Existing crates are never
+/// overwritten.
+///
+/// # Arguments
+/// * `name` - Dash-separated program name, will prompt if None
+/// * `options` - Template, location and answers
+///
+/// # Returns
+/// Result indicating success or failure
+pub async fn add_program(name: Option<String>, options: AddProgramOptions) -> Result<()> {
+    let user_config = UserConfig::load()?;
+    let root = find_workspace_root(options.path.as_deref().unwrap_or(Path::new(".")))?;
+    let (template, manifest) = load_template(options.template_path, &user_config)?;
+
+    let programs = workspace_programs(&root)?;
+    if let Some(name) = &name {
+        validate_new_program(&root, &programs, name)?;
+    }
+    let mut answers = options.answers;
+    inherit_license(&mut answers, &programs);
+
+    let keystore = keystore_dir(false, &user_config)?;
+    let mut config = ProjectConfig::build(
+        name,
+        &manifest,
+        &answers,
+        &user_config,
+        &KeypairOptions::default(),
+        keystore.as_deref(),
+    )
+    .await?;
+    validate_new_program(&root, &programs, &config.program_name_dash)?;
+
+    let program_dir = Path::new(PROGRAMS_DIR).join(&config.program_name_dash);
+    // Every program keeps its keypair in its own crate, unless in the keystore
+    if config
+        .program_keypair_path
+        .as_ref()
+        .is_some_and(|p| p.is_relative())
+    {
+        config.program_keypair_path = Some(program_dir.join(PROGRAM_ID_FILE));
+    }
+    // The deploy command reuses the workspace's deployer
+    let deployer_file = root.join(DEPLOYER_FILE);
+    if deployer_file.is_file() {
+        config.deployer_pubkey = Some(read_keypair_file(&deployer_file)?.pubkey().to_string());
+        config.deployer_keypair_path = Some(PathBuf::from(DEPLOYER_FILE));
+    }
+
+    println!(
+        "{} Adding program {} to {}",
+        style("✨").green(),
+        style(&config.program_name_dash).cyan().bold(),
+        style(root.display()).cyan()
+    );
+    print_template_source(&template);
+
+    let files = render_project_files(&template, &manifest, &config)?
+        .into_iter()
+        .filter(|file| {
+            file.action != FileAction::Skipped && file.output_path.starts_with(&program_dir)
+        })
+        .collect::<Vec<_>>();
+    if files.is_empty() {
+        return Err(anyhow::anyhow!(
+            "The template has no program to add, expected files in '{}/{{{{program_name_dash}}}}'",
+            PROGRAMS_DIR
+        ));
+    }
+    let deploy = render_partial(&template, &config, DEPLOY_PARTIAL)?;
+
+    // Generate into a staging directory, removed again on error or Ctrl-C
+    let staging = StagingDir::create(&root.join(&program_dir))?;
+    staging.remove_on_interrupt();
+    files.iter().try_for_each(|file| {
+        let relative = file.output_path.strip_prefix(&program_dir)?;
+        write_rendered_file(&staging.path().join(relative), file)
+    })?;
+    // The program keypair lives in the new crate, so it is staged along with it
+    if let (Some(path), Some(keypair)) = (&config.program_keypair_path, &config.program_keypair) {
+        if path.is_relative() {
+            write_keypair_file(
+                &staging.path().join(path.strip_prefix(&program_dir)?),
+                keypair,
+            )?;
+        }
+    }
+    // Keystore keypairs are written before the crate is moved into place, so a
+    // failure rolls it back instead of leaving it pointing at a missing file
+    store_keypairs(&config)?;
+    staging.commit()?;
+
+    for workflow in extend_workflows(&root, &config.program_name_dash)? {
+        println!(
+            "{} Added {} to the CI matrix in {}",
+            style("✔").green(),
+            config.program_name_dash,
+            workflow.display()
+        );
+    }
+    let readme_updated = match &deploy {
+        Some(deploy) => extend_readme(&root, &config, deploy)?,
+        None => false,
+    };
+    if readme_updated {
+        println!(
+            "{} Added its deploy command to README.md",
+            style("✔").green()
+        );
+    } else if deploy.is_some() {
+        println!(
+            "{} README.md has no {} line, deploy commands were not added",
+            style("⚠️").yellow(),
+            README_DEPLOY_MARKER
+        );
+    }
+
+    if !is_workspace_member(&root, &program_dir)? {
+        println!(
+            "{} Add \"{}\" to the workspace members in Cargo.toml",
+            style("⚠️").yellow(),
+            program_dir.display()
+        );
+    }
+    println!(
+        "{} Program created in {}",
+        style("✅").green(),
+        style(program_dir.display()).cyan()
+    );
+    println!(
+        "{} Program ID: {}",
+        style("🔑").yellow(),
+        style(&config.program_pubkey).cyan()
+    );
+    Ok(())
+}
+
+/// Options of the `add instruction` command
+#[derive(Debug, Default)]
+pub struct AddInstructionOptions {
+    /// Program to add the instruction to, by directory name below `programs/`
+    pub program: Option<String>,
+    /// Directory inside the workspace, defaults to the current directory
+    pub path: Option<PathBuf>,
+    /// Accounts in the order the instruction expects them
+    pub accounts: Vec<AccountSpec>,
+    /// Arguments in the order of the instruction data
+    pub args: Vec<ArgSpec>,
+    /// Whether prompts may be shown
+    pub interactive: bool,
+}
+
+/// Options of the `add state` command
+#[derive(Debug, Default)]
+pub struct AddStateOptions {
+    /// Program to add the state to, by directory name below `programs/`
+    pub program: Option<String>,
+    /// Directory inside the workspace, defaults to the current directory
+    pub path: Option<PathBuf>,
+    /// Fields after the key and bump, in layout order
+    pub fields: Vec<FieldSpec>,
+    /// Seeds of the PDA holding the state, none if it is not a PDA
+    pub seeds: Vec<SeedSpec>,
+    /// Whether prompts may be shown
+    pub interactive: bool,
+}
+
+/// Picks the program crate a generator writes to
+///
+/// Without a name, the program containing `start` is used, or the only
+/// program of the workspace, or the one the user selects.
+///
+/// # Arguments
+/// * `root` - Workspace root
+/// * `start` - Directory the command runs in
+/// * `name` - Directory name of the program below `programs/`, if given
+/// * `interactive` - Whether the user may be asked to pick one
+///
+/// # Returns
+/// The directory of the program crate
+pub(crate) fn select_program(
+    root: &Path,
+    start: &Path,
+    name: Option<&str>,
+    interactive: bool,
+) -> Result<PathBuf> {
+    let programs = workspace_programs(root)?;
+    let names = programs
+        .iter()
+        .map(|program| {
+            program
+                .dir
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+        })
+        .collect::<Vec<_>>();
+    if programs.is_empty() {
+        return Err(anyhow::anyhow!(
+            "'{}' has no programs, add one with `gepetto add program`",
+            root.join(PROGRAMS_DIR).display()
+        ));
+    }
+
+    if let Some(name) = name {
+        return programs
+            .iter()
+            .find(|program| program.dir.file_name().is_some_and(|dir| dir == name))
+            .map(|program| program.dir.clone())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No program '{}' in the workspace, expected one of: {}",
+                    name,
+                    names.join(", ")
+                )
+            });
+    }
+    let start = fs::canonicalize(start)?;
+    if let Some(program) = programs
+        .iter()
+        .find(|program| start.starts_with(&program.dir))
+    {
+        return Ok(program.dir.clone());
+    }
+    if let [program] = programs.as_slice() {
+        return Ok(program.dir.clone());
+    }
+    if !interactive {
+        return Err(Answers::missing(
+            "program to add to",
+            &format!("pass --program with one of: {}", names.join(", ")),
+        ));
+    }
+    let index = Select::new()
+        .with_prompt("Program")
+        .items(&names)
+        .default(0)
+        .interact()?;
+    Ok(programs[index].dir.clone())
+}
+
+/// Reads a source file of the program, with its path in errors
+fn read_source(path: &Path) -> Result<String> {
+    fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read '{}': {}", path.display(), e))
+}
+
+/// Formats generated sources with rustfmt before they are written
+///
+/// New files are always formatted. An existing file is only formatted if it
+/// already was before the change, so code gepetto did not write is never
+/// reformatted. Formatting is cosmetic, so a missing or failing rustfmt is
+/// only reported.
+///
+/// # Arguments
+/// * `changes` - Paths and new contents of the changed files
+/// * `edition` - Rust edition of the crate, as in its `Cargo.toml`
+fn format_sources(changes: &mut [(PathBuf, String)], edition: &str) {
+    let mut failed = false;
+    for (path, contents) in changes.iter_mut() {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        if let Ok(original) = fs::read_to_string(&*path) {
+            if rustfmt(&original, edition, dir).as_ref() != Some(&original) {
+                continue;
+            }
+        }
+        match rustfmt(contents, edition, dir) {
+            Some(formatted) => *contents = formatted,
+            None => failed = true,
+        }
+    }
+    if failed {
+        println!(
+            "{} rustfmt could not format the generated code, run `cargo fmt`",
+            style("⚠️").yellow()
+        );
+    }
+}
+
+/// Appends a generated item to a source file, after a blank line
+fn append_item(source: String, item: &str) -> String {
+    format!("{}\n\n{}\n", source.trim_end(), item.trim())
+}
+
+/// Runs rustfmt over a source, picking up the `rustfmt.toml` of `dir`
+///
+/// # Returns
+/// The formatted source, or None if rustfmt is missing or failed
+fn rustfmt(source: &str, edition: &str, dir: &Path) -> Option<String> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new("rustfmt")
+        .args(["--edition", edition, "--emit", "stdout", "--quiet"])
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    // Write from another thread, so a full stdout pipe cannot block rustfmt
+    let mut stdin = child.stdin.take()?;
+    let input = source.to_string();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output().ok()?;
+    writer.join().ok()?.ok()?;
+    match output.status.success() {
+        true => String::from_utf8(output.stdout).ok(),
+        false => None,
+    }
+}
+
+/// Reads the Rust edition of a program crate
+///
+/// # Arguments
+/// * `root` - Workspace root, consulted for `edition.workspace = true`
+/// * `program_dir` - Directory of the program crate
+///
+/// # Returns
+/// The edition, Cargo's default of 2015 if none is set
+fn crate_edition(root: &Path, program_dir: &Path) -> Result<String> {
+    let read = |path: &Path| -> Result<toml::Table> {
+        toml::from_str(&read_source(path)?)
+            .map_err(|e| anyhow::anyhow!("Failed to parse '{}': {}", path.display(), e))
+    };
+    let manifest = read(&program_dir.join("Cargo.toml"))?;
+    let edition = manifest
+        .get("package")
+        .and_then(|package| package.get("edition"));
+    let edition = match edition {
+        Some(toml::Value::Table(edition)) if edition.get("workspace").is_some() => {
+            read(&root.join("Cargo.toml"))?
+                .get("workspace")
+                .and_then(|workspace| workspace.get("package"))
+                .and_then(|package| package.get("edition"))
+                .and_then(|edition| edition.as_str().map(str::to_string))
+        }
+        Some(edition) => edition.as_str().map(str::to_string),
+        None => None,
+    };
+    Ok(edition.unwrap_or_else(|| "2015".to_string()))
+}
+
+/// Adds an instruction to a program of the workspace
+///
+/// Generates `src/instructions/<name>.rs` in the style of the template's
+/// instructions, with the next free discriminator, and wires it into
+/// `instructions/mod.rs`, the entrypoint, the Mollusk tests and the compute
+/// unit benchmarks. Every change is prepared before any file is written.
+///
+/// # Arguments
+/// * `name` - Instruction name, e.g. `withdraw`
+/// * `options` - Program, accounts and arguments of the instruction
+///
+/// # Returns
+/// Result indicating success or failure
+pub fn add_instruction(name: &str, options: AddInstructionOptions) -> Result<()> {
+    let start = options.path.unwrap_or_else(|| PathBuf::from("."));
+    let root = find_workspace_root(&start)?;
+    let program_dir = select_program(
+        &root,
+        &start,
+        options.program.as_deref(),
+        options.interactive,
+    )?;
+    let edition = crate_edition(&root, &program_dir)?;
+    let format_item = |item: String| rustfmt(&item, &edition, &program_dir).unwrap_or(item);
+    let src = program_dir.join("src");
+    let instructions_dir = src.join("instructions");
+    if !instructions_dir.is_dir() {
+        return Err(anyhow::anyhow!(
+            "'{}' not found, the program does not follow the template layout",
+            instructions_dir.display()
+        ));
+    }
+
+    let mut handlers = Vec::new();
+    for entry in fs::read_dir(&instructions_dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "rs") {
+            handlers.push(read_source(&path)?);
+        }
+    }
+    let discriminator = next_discriminator(handlers.iter().map(String::as_str))?;
+
+    let (accounts, args) = match (options.accounts, options.args) {
+        (accounts, args) if !accounts.is_empty() || !options.interactive => (accounts, args),
+        (_, args) => (
+            prompt_accounts()?,
+            if args.is_empty() {
+                prompt_args()?
+            } else {
+                args
+            },
+        ),
+    };
+    let spec = InstructionSpec::new(name, discriminator, accounts, args)?;
+
+    let handler_path = instructions_dir.join(format!("{}.rs", spec.name));
+    if handler_path.exists() {
+        return Err(anyhow::anyhow!(
+            "'{}' already exists, pick another instruction name",
+            handler_path.display()
+        ));
+    }
+    let struct_re = Regex::new(&format!(r"\bstruct\s+{}\b", spec.type_name))?;
+    if handlers.iter().any(|handler| struct_re.is_match(handler)) {
+        return Err(anyhow::anyhow!(
+            "Instruction '{}' already exists in '{}'",
+            spec.type_name,
+            instructions_dir.display()
+        ));
+    }
+
+    // Prepare every change first, so a missing piece stops before any write
+    let mod_path = instructions_dir.join("mod.rs");
+    let entrypoint_path = src.join("entrypoint.rs");
+    let entrypoint = read_source(&entrypoint_path)?;
+    let entrypoint = add_import(&entrypoint, "instructions", &spec.type_name)
+        .and_then(|entrypoint| add_entrypoint_arm(&entrypoint, &spec))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "'{}' has no `use crate::instructions::...` or no `_ =>` match arm to extend",
+                entrypoint_path.display()
+            )
+        })?;
+    let mut changes = vec![
+        (handler_path.clone(), spec.handler()?),
+        (
+            mod_path.clone(),
+            add_module(&read_source(&mod_path)?, &spec.name),
+        ),
+        (entrypoint_path.clone(), entrypoint),
+    ];
+
+    let test_path = program_dir.join("tests").join("mollusk_tests.rs");
+    let test = fs::read_to_string(&test_path)
+        .ok()
+        .and_then(|test| add_import(&test, "instructions", &spec.type_name))
+        .map(|test| {
+            spec.test()
+                .map(|generated| append_item(test, &format_item(generated)))
+        })
+        .transpose()?;
+    match test {
+        Some(test) => changes.push((test_path.clone(), test)),
+        None => println!(
+            "{} No Mollusk tests in {}, skipping the test",
+            style("⚠️").yellow(),
+            test_path.display()
+        ),
+    }
+
+    let bench_path = program_dir.join("benches").join("compute_units.rs");
+    let bench = match fs::read_to_string(&bench_path)
+        .ok()
+        .and_then(|bench| add_import(&bench, "instructions", &spec.type_name))
+    {
+        Some(bench) => add_bench(&bench, &spec, &format_item(spec.bench()?)),
+        None => None,
+    };
+    match bench {
+        Some(bench) => changes.push((bench_path.clone(), bench)),
+        None => println!(
+            "{} No compute unit benchmark in {}, skipping the benchmark",
+            style("⚠️").yellow(),
+            bench_path.display()
+        ),
+    }
+
+    format_sources(&mut changes, &edition);
+    for (path, contents) in &changes {
+        fs::write(path, contents)
+            .map_err(|e| anyhow::anyhow!("Failed to write '{}': {}", path.display(), e))?;
+    }
+
+    let relative = |path: &Path| {
+        path.strip_prefix(&root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    println!(
+        "{} Instruction {} added with discriminator {}",
+        style("✅").green(),
+        style(&spec.type_name).cyan().bold(),
+        spec.discriminator
+    );
+    for (path, _) in &changes {
+        let verb = if *path == handler_path {
+            "created"
+        } else {
+            "updated"
+        };
+        println!("   {} {}", style(verb).dim(), relative(path));
+    }
+    Ok(())
+}
+
+/// Asks for the accounts of an instruction, one per prompt
+fn prompt_accounts() -> Result<Vec<AccountSpec>> {
+    let mut accounts = Vec::new();
+    loop {
+        let name = Input::<String>::new()
+            .with_prompt("Account name (empty when done)")
+            .allow_empty(true)
+            .validate_with(|name: &String| {
+                if name.trim().is_empty() {
+                    return Ok(());
+                }
+                AccountSpec::new(name, &[])
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            })
+            .interact_text()?;
+        if name.trim().is_empty() {
+            break;
+        }
+        let flags = match name.trim().to_snake_case().as_str() {
+            "system_program" => Vec::new(),
+            _ => MultiSelect::new()
+                .with_prompt(format!("Checks of '{}' (space to toggle)", name.trim()))
+                .items(&ACCOUNT_FLAGS[..4])
+                .interact()?
+                .into_iter()
+                .map(|i| ACCOUNT_FLAGS[i])
+                .collect(),
+        };
+        accounts.push(AccountSpec::new(&name, &flags)?);
+    }
+    Ok(accounts)
+}
+
+/// Asks for the arguments of an instruction, one per prompt
+fn prompt_args() -> Result<Vec<ArgSpec>> {
+    let mut args = Vec::new();
+    loop {
+        let arg = Input::<String>::new()
+            .with_prompt("Argument as name:type, e.g. amount:u64 (empty when done)")
+            .allow_empty(true)
+            .validate_with(|arg: &String| {
+                if arg.trim().is_empty() {
+                    return Ok(());
+                }
+                arg.parse::<ArgSpec>()
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            })
+            .interact_text()?;
+        if arg.trim().is_empty() {
+            break;
+        }
+        args.push(arg.parse()?);
+    }
+    Ok(args)
+}
+
+/// Adds a state account type to a program of the workspace
+///
+/// Generates `src/state/<name>.rs` in the style of the template's `Counter`,
+/// with `LEN`, `load`, `load_mut`, `set_inner` and, for PDAs, the seed
+/// functions. Multi-byte fields are stored as byte arrays so the layout has
+/// no padding. The type gets the next `StateKey` value, is exported from
+/// `state/mod.rs`, and a Mollusk test checks its layout round-trips.
+///
+/// # Arguments
+/// * `name` - State name, e.g. `vault`
+/// * `options` - Program, fields and seeds of the state
+///
+/// # Returns
+/// Result indicating success or failure
+pub fn add_state(name: &str, options: AddStateOptions) -> Result<()> {
+    let start = options.path.unwrap_or_else(|| PathBuf::from("."));
+    let root = find_workspace_root(&start)?;
+    let program_dir = select_program(
+        &root,
+        &start,
+        options.program.as_deref(),
+        options.interactive,
+    )?;
+    let edition = crate_edition(&root, &program_dir)?;
+    let format_item = |item: String| rustfmt(&item, &edition, &program_dir).unwrap_or(item);
+    let state_dir = program_dir.join("src").join("state");
+    if !state_dir.is_dir() {
+        return Err(anyhow::anyhow!(
+            "'{}' not found, the program does not follow the template layout",
+            state_dir.display()
+        ));
+    }
+
+    let (fields, seeds) = match (options.fields, options.seeds) {
+        (fields, seeds) if !fields.is_empty() || !options.interactive => (fields, seeds),
+        (_, seeds) => (
+            prompt_fields()?,
+            if seeds.is_empty() {
+                prompt_seeds()?
+            } else {
+                seeds
+            },
+        ),
+    };
+    let spec = StateSpec::new(name, fields, seeds)?;
+
+    let state_path = state_dir.join(format!("{}.rs", spec.name));
+    if state_path.exists() {
+        return Err(anyhow::anyhow!(
+            "'{}' already exists, pick another state name",
+            state_path.display()
+        ));
+    }
+    let mut sources = Vec::new();
+    for entry in fs::read_dir(&state_dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "rs") {
+            let source = read_source(&path)?;
+            sources.push((path, source));
+        }
+    }
+    let struct_re = Regex::new(&format!(r"\bstruct\s+{}\b", spec.type_name))?;
+    if sources.iter().any(|(_, source)| struct_re.is_match(source)) {
+        return Err(anyhow::anyhow!(
+            "State '{}' already exists in '{}'",
+            spec.type_name,
+            state_dir.display()
+        ));
+    }
+
+    // Prepare every change first, so a missing piece stops before any write
+    let mut state_key = None;
+    for (path, source) in &sources {
+        if let Some((updated, key)) = add_state_key(source, &spec.type_name)? {
+            state_key = Some((path.clone(), updated, key));
+            break;
+        }
+    }
+    let (key_path, key_source, key) = state_key.ok_or_else(|| {
+        anyhow::anyhow!(
+            "No `enum StateKey` found in '{}' to add the {} variant to",
+            state_dir.display(),
+            spec.type_name
+        )
+    })?;
+    let mod_path = state_dir.join("mod.rs");
+    let mut changes = vec![
+        (state_path.clone(), spec.source()?),
+        (
+            mod_path.clone(),
+            add_module(&read_source(&mod_path)?, &spec.name),
+        ),
+        (key_path, key_source),
+    ];
+
+    let test_path = program_dir.join("tests").join("mollusk_tests.rs");
+    let test = fs::read_to_string(&test_path)
+        .ok()
+        .and_then(|test| add_import(&test, "state", &spec.type_name))
+        .and_then(|test| add_import(&test, "state", "StateKey"))
+        .map(|test| {
+            spec.test()
+                .map(|generated| append_item(test, &format_item(generated)))
+        })
+        .transpose()?;
+    match test {
+        Some(test) => changes.push((test_path.clone(), test)),
+        None => println!(
+            "{} No Mollusk tests importing the state in {}, skipping the test",
+            style("⚠️").yellow(),
+            test_path.display()
+        ),
+    }
+
+    format_sources(&mut changes, &edition);
+    for (path, contents) in &changes {
+        fs::write(path, contents)
+            .map_err(|e| anyhow::anyhow!("Failed to write '{}': {}", path.display(), e))?;
+    }
+
+    let relative = |path: &Path| {
+        path.strip_prefix(&root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    println!(
+        "{} State {} added with StateKey {}",
+        style("✅").green(),
+        style(&spec.type_name).cyan().bold(),
+        key
+    );
+    for (path, _) in &changes {
+        let verb = if *path == state_path {
+            "created"
+        } else {
+            "updated"
+        };
+        println!("   {} {}", style(verb).dim(), relative(path));
+    }
+    Ok(())
+}
+
+/// Asks for the fields of a state type, one per prompt
+fn prompt_fields() -> Result<Vec<FieldSpec>> {
+    let mut fields = Vec::new();
+    loop {
+        let field = Input::<String>::new()
+            .with_prompt("Field as name:type, e.g. amount:u64 (empty when done)")
+            .allow_empty(true)
+            .validate_with(|field: &String| {
+                if field.trim().is_empty() {
+                    return Ok(());
+                }
+                field
+                    .parse::<FieldSpec>()
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            })
+            .interact_text()?;
+        if field.trim().is_empty() {
+            break;
+        }
+        fields.push(field.parse()?);
+    }
+    Ok(fields)
+}
+
+/// Asks for the seeds of a state PDA, one per prompt
+fn prompt_seeds() -> Result<Vec<SeedSpec>> {
+    let mut seeds = Vec::new();
+    loop {
+        let seed = Input::<String>::new()
+            .with_prompt(
+                "PDA seed, a literal like vault or name:type like authority:Pubkey (empty when done)",
+            )
+            .allow_empty(true)
+            .validate_with(|seed: &String| {
+                if seed.trim().is_empty() {
+                    return Ok(());
+                }
+                seed.parse::<SeedSpec>()
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            })
+            .interact_text()?;
+        if seed.trim().is_empty() {
+            break;
+        }
+        seeds.push(seed.parse()?);
+    }
+    Ok(seeds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORKFLOW: &str =
+        include_str!("../../template/.github/workflows/build-test-and-benchmark.yml");
+
+    #[test]
+    fn test_extend_program_matrix() {
+        let mut context = tera::Context::new();
+        context.insert("program_name_dash", "counter");
+        let workflow = tera::Tera::one_off(WORKFLOW, &context, false).unwrap();
+        assert!(workflow.contains("        program:\n          - counter\n\n    steps:"));
+
+        let extended = extend_program_matrix(&workflow, "vault").unwrap();
+        assert_eq!(
+            extended,
+            workflow.replace(
+                "          - counter\n",
+                "          - counter\n          - vault\n"
+            )
+        );
+        assert!(extended.ends_with('\n'));
+
+        // The trailing newline is kept as it was
+        let workflow = "matrix:\n  program:\n    - counter";
+        assert_eq!(
+            extend_program_matrix(workflow, "vault").as_deref(),
+            Some("matrix:\n  program:\n    - counter\n    - vault")
+        );
+    }
+
+    #[test]
+    fn test_extend_program_matrix_without_list() {
+        for workflow in [
+            "jobs:\n  build:\n    runs-on: ubuntu-latest\n",
+            "matrix:\n  program: []\n",
+            "matrix:\n  program:\n  os: [linux]\n",
+            "",
+        ] {
+            assert_eq!(
+                extend_program_matrix(workflow, "vault"),
+                None,
+                "{}",
+                workflow
+            );
+        }
+    }
+
+    #[test]
+    fn test_add_readme_section() {
+        let readme = "## Deploy\n\nCounter:\n\n```sh\n$ deploy counter\n```\n\n<!-- gepetto:deploy -->\n\n### Test\n";
+        assert_eq!(
+            add_readme_section(readme, "Vault", "```sh\n$ deploy vault\n```\n\n").as_deref(),
+            Some(
+                "## Deploy\n\nCounter:\n\n```sh\n$ deploy counter\n```\n\nVault:\n\n```sh\n$ deploy vault\n```\n\n<!-- gepetto:deploy -->\n\n### Test\n"
+            )
+        );
+        assert_eq!(add_readme_section("## Deploy\n", "Vault", "deploy"), None);
+    }
+}
@@ -0,0 +1,60 @@
+use anyhow::Result;
+use console::style;
+
+use crate::user_config::{UserConfig, CONFIG_KEYS};
+
+/// Prints the value of a user config key, or nothing if it is unset
+///
+/// # Arguments
+/// * `key` - Config key to read
+///
+/// # Returns
+/// Result indicating success or failure
+pub fn config_get(key: &str) -> Result<()> {
+    let config = UserConfig::load()?;
+    if let Some(value) = config.get(key)? {
+        println!("{}", value);
+    }
+    Ok(())
+}
+
+/// Sets a user config key and saves the config file
+///
+/// # Arguments
+/// * `key` - Config key to write
+/// * `value` - New value, an empty value unsets the key
+///
+/// # Returns
+/// Result indicating success or failure
+pub fn config_set(key: &str, value: &str) -> Result<()> {
+    let mut config = UserConfig::load()?;
+    config.set(key, value)?;
+    let path = config.save()?;
+    println!(
+        "{} {} saved to {}",
+        style("✔").green(),
+        style(key).cyan(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Prints every user config key with its value
+///
+/// # Returns
+/// Result indicating success or failure
+pub fn config_list() -> Result<()> {
+    let config = UserConfig::load()?;
+    println!(
+        "{} {}",
+        style("⚙️").blue(),
+        style(UserConfig::path()?.display()).dim()
+    );
+    for key in CONFIG_KEYS {
+        match config.get(key)? {
+            Some(value) => println!("{} = {}", style(key).cyan(), value),
+            None => println!("{} {}", style(key).cyan(), style("(unset)").dim()),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use console::style;
+use solana_sdk::signature::Signer;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::answers::Answers;
+use crate::commands::new::{load_template, print_template_source};
+use crate::config::{KeypairOptions, ProgramKeypairSource, ProjectConfig};
+use crate::hooks::run_post_generation;
+use crate::io::{print_success_message, prompt_conflict};
+use crate::keypair::{keystore_dir, read_keypair_file, store_keypairs, PROGRAM_ID_FILE};
+use crate::template::{
+    create_keypair_files, create_template_context, render_project_files, write_rendered_file,
+    FileAction,
+};
+use crate::user_config::UserConfig;
+
+/// How to handle a generated file that already exists in the target directory
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ConflictPolicy {
+    /// Keep the existing file
+    Skip,
+    /// Replace the existing file with the generated one
+    Overwrite,
+    /// Keep the existing file and write the generated one next to it as `<name>.gepetto-new`
+    KeepBoth,
+    /// Ask for every conflicting file, with the option to see a diff
+    #[default]
+    Prompt,
+}
+
+/// Options of the `init` command
+#[derive(Debug, Default)]
+pub struct InitOptions {
+    /// Answers given with flags, env vars or an answers file
+    pub answers: Answers,
+    /// Custom template directory, uses the built-in template if None
+    pub template_path: Option<PathBuf>,
+    /// What to do with files that already exist
+    pub conflict_policy: ConflictPolicy,
+    /// Skip the post-generation hooks (git init, template commands)
+    pub no_hooks: bool,
+    /// How to obtain the program and deployer keypairs
+    pub keypairs: KeypairOptions,
+}
+
+/// Number of files per outcome, reported once the project is initialized
+#[derive(Debug, Default)]
+struct InitSummary {
+    created: usize,
+    unchanged: usize,
+    overwritten: usize,
+    skipped: usize,
+    kept_both: usize,
+}
+
+/// Scaffolds a Pinocchio project into an existing directory
+///
+/// Works like `cargo init`: files already present in the directory, such as a
+/// README or LICENSE created by a git host, are resolved one by one with the
+/// given conflict policy instead of failing the whole command.
+///
+/// # Arguments
+/// * `path` - Target directory, defaults to the current directory
+/// * `package_name` - Package name, defaults to the directory name
+/// * `options` - Template, conflict and hook options
+///
+/// # Returns
+/// Result indicating success or failure of the scaffolding process
+pub async fn init_project(
+    path: Option<PathBuf>,
+    package_name: Option<String>,
+    options: InitOptions,
+) -> Result<()> {
+    let project_dir = path.unwrap_or_else(|| PathBuf::from("."));
+    if project_dir.exists() && !project_dir.is_dir() {
+        return Err(anyhow::anyhow!(
+            "'{}' is not a directory",
+            project_dir.display()
+        ));
+    }
+    // An existing program-id.json is kept, as long as it is the same program
+    let program_id_file = project_dir.join(PROGRAM_ID_FILE);
+    if program_id_file.exists() {
+        let program_id = match &options.keypairs.program {
+            ProgramKeypairSource::Existing(keypair) => keypair.pubkey(),
+            ProgramKeypairSource::ProgramId(program_id) => *program_id,
+            ProgramKeypairSource::Generate | ProgramKeypairSource::Vanity { .. } => {
+                return Err(anyhow::anyhow!(
+                    "'{}' already contains a program-id.json, pass --program-keypair {} to keep it or move it away to initialize a new program",
+                    project_dir.display(),
+                    program_id_file.display()
+                ));
+            }
+        };
+        let existing = read_keypair_file(&program_id_file)?.pubkey();
+        if existing != program_id {
+            return Err(anyhow::anyhow!(
+                "'{}' belongs to program {}, not {}",
+                program_id_file.display(),
+                existing,
+                program_id
+            ));
+        }
+    }
+
+    let user_config = UserConfig::load()?;
+    let (template, manifest) = load_template(options.template_path, &user_config)?;
+
+    let keystore = keystore_dir(options.keypairs.keystore, &user_config)?;
+
+    let package_name = match package_name.or_else(|| options.answers.name.clone()) {
+        Some(name) => name,
+        None => directory_name(&project_dir)?,
+    };
+    let config = ProjectConfig::build(
+        Some(package_name),
+        &manifest,
+        &options.answers,
+        &user_config,
+        &options.keypairs,
+        keystore.as_deref(),
+    )
+    .await?;
+
+    println!(
+        "{} Initializing Pinocchio project {} in {}",
+        style("✨").green(),
+        style(&config.program_name_dash).cyan().bold(),
+        style(project_dir.display()).cyan()
+    );
+    print_template_source(&template);
+
+    let files = render_project_files(&template, &manifest, &config)?;
+
+    // Every conflict is resolved before anything is written, so a missing
+    // decision or a declined prompt leaves the directory as it was
+    let mut summary = InitSummary::default();
+    let mut writes = Vec::new();
+    for file in files.iter().filter(|f| f.action != FileAction::Skipped) {
+        let dest_path = project_dir.join(&file.output_path);
+
+        if !dest_path.exists() {
+            writes.push((dest_path, file));
+            summary.created += 1;
+            continue;
+        }
+
+        let existing = fs::read(&dest_path)?;
+        if existing == file.contents {
+            summary.unchanged += 1;
+            continue;
+        }
+
+        let policy = match options.conflict_policy {
+            ConflictPolicy::Prompt if !options.answers.interactive => {
+                return Err(Answers::missing(
+                    &format!(
+                        "decision for existing file '{}'",
+                        file.output_path.display()
+                    ),
+                    "pass --conflict skip, overwrite or keep-both",
+                ));
+            }
+            ConflictPolicy::Prompt => {
+                prompt_conflict(&file.output_path, &existing, &file.contents)?
+            }
+            policy => policy,
+        };
+        match policy {
+            ConflictPolicy::Skip | ConflictPolicy::Prompt => summary.skipped += 1,
+            ConflictPolicy::Overwrite => {
+                writes.push((dest_path, file));
+                summary.overwritten += 1;
+            }
+            ConflictPolicy::KeepBoth => {
+                let mut new_name = dest_path.into_os_string();
+                new_name.push(".gepetto-new");
+                writes.push((PathBuf::from(new_name), file));
+                summary.kept_both += 1;
+            }
+        }
+    }
+
+    // Keypairs next, so the files never point at a keystore entry that was
+    // not written and a clash with an existing keypair stops before any file changes
+    fs::create_dir_all(&project_dir)?;
+    store_keypairs(&config)?;
+    create_keypair_files(&project_dir, &config)?;
+
+    for (dest_path, file) in writes {
+        write_rendered_file(&dest_path, file)?;
+    }
+
+    println!(
+        "{} {} created, {} unchanged, {} overwritten, {} skipped, {} kept both",
+        style("📝").blue(),
+        summary.created,
+        summary.unchanged,
+        summary.overwritten,
+        summary.skipped,
+        summary.kept_both
+    );
+    print_success_message(&config.program_pubkey, config.deployer_pubkey.as_deref());
+
+    if !options.no_hooks {
+        run_post_generation(&project_dir, &manifest, &create_template_context(&config))?;
+    }
+
+    Ok(())
+}
+
+/// Returns the name of a directory, resolving `.` and relative paths
+fn directory_name(dir: &Path) -> Result<String> {
+    let absolute = if dir.exists() {
+        dir.canonicalize()?
+    } else {
+        std::env::current_dir()?.join(dir)
+    };
+    absolute
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Cannot derive a package name from '{}', pass one with --name",
+                dir.display()
+            )
+        })
+}
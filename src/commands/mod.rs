@@ -1,3 +1,9 @@
+pub mod add;
+pub mod config;
+pub mod init;
 pub mod new;
 
+pub use add::*;
+pub use config::*;
+pub use init::*;
 pub use new::*;
@@ -1,31 +1,152 @@
 use anyhow::Result;
 use console::style;
-use std::fs;
-use std::path::Path;
-use tera::Tera;
+use std::path::{Path, PathBuf};
 
-use crate::config::ProjectConfig;
-use crate::io::print_success_message;
-use crate::template::{copy_template_files, create_program_id_file, create_template_context};
-use crate::validation::{validate_project_directory, validate_template_directory};
+use crate::answers::Answers;
+use crate::config::{KeypairOptions, ProjectConfig};
+use crate::hooks::run_post_generation;
+use crate::io::{collect_program_name, print_dry_run, print_success_message};
+use crate::keypair::{keystore_dir, store_keypairs};
+use crate::manifest::TemplateManifest;
+use crate::staging::StagingDir;
+use crate::template::{
+    create_keypair_files, create_template_context, render_project_files, write_rendered_files,
+    TemplateSource,
+};
+use crate::user_config::UserConfig;
+use crate::validation::{
+    validate_parent_directory, validate_project_directory, validate_template_directory,
+};
+
+/// Options of the `new` command besides the package name
+#[derive(Debug, Default)]
+pub struct NewOptions {
+    /// Answers given with flags, env vars or an answers file
+    pub answers: Answers,
+    /// Custom template directory, uses the built-in template if None
+    pub template_path: Option<PathBuf>,
+    /// Exact project directory, independent of the program name
+    pub path: Option<PathBuf>,
+    /// Directory the project is created in as `<output_dir>/<name>`
+    pub output_dir: Option<PathBuf>,
+    /// Print what would be generated without writing anything
+    pub dry_run: bool,
+    /// With `dry_run`, also print a diff of every templated file
+    pub show_diff: bool,
+    /// Skip the post-generation hooks (git init, template commands)
+    pub no_hooks: bool,
+    /// How to obtain the program and deployer keypairs
+    pub keypairs: KeypairOptions,
+}
+
+/// Where the project directory goes
+enum ProjectLocation {
+    /// This exact directory
+    Exact(PathBuf),
+    /// A directory named after the program inside this one
+    Under(PathBuf),
+}
+
+/// Expands `~` and environment variables in a path given by the user
+fn expand_path(path: &Path) -> Result<PathBuf> {
+    let path = path.to_string_lossy();
+    let expanded =
+        shellexpand::full(&path).map_err(|e| anyhow::anyhow!("Cannot expand '{}': {}", path, e))?;
+    Ok(PathBuf::from(expanded.into_owned()))
+}
+
+/// Resolves the template to use and loads its manifest
+///
+/// # Arguments
+/// * `template_path` - Optional custom template directory
+/// * `user_config` - User defaults, whose `template` is used if no path is given
+///
+/// # Returns
+/// The validated template source and its manifest
+pub(crate) fn load_template(
+    template_path: Option<PathBuf>,
+    user_config: &UserConfig,
+) -> Result<(TemplateSource, TemplateManifest)> {
+    let template_path = match template_path {
+        Some(path) => Some(path),
+        None => user_config.path_value("template")?,
+    };
+    let template = TemplateSource::resolve(template_path);
+    validate_template_directory(&template)?;
+    let manifest = TemplateManifest::load(&template)?;
+    Ok((template, manifest))
+}
+
+/// Tells the user when a custom template is in use
+pub(crate) fn print_template_source(template: &TemplateSource) {
+    if let TemplateSource::Local(_) = template {
+        println!(
+            "{} Using template: {}",
+            style("📦").blue(),
+            style(template.describe()).cyan()
+        );
+    }
+}
 
 /// Main function to scaffold a new Pinocchio project
 ///
 /// This function orchestrates the entire project creation process:
-/// 1. Collects user input
-/// 2. Generates project configuration
-/// 3. Validates directories
-/// 4. Creates project structure from templates
-/// 5. Generates program keypair file
+/// 1. Resolves and validates the template and its manifest
+/// 2. Collects the program name and validates the project directory
+/// 3. Collects the remaining input and generates the project configuration
+/// 4. Renders the templates, printing them instead in dry-run mode
+/// 5. Writes the project structure and program keypair file to a staging
+///    directory, then moves it into place
+/// 6. Runs the post-generation hooks
 ///
 /// # Arguments
 /// * `package_name` - Optional package name, will prompt if None
+/// * `options` - Template, dry-run and hook options
 ///
 /// # Returns
 /// Result indicating success or failure of the scaffolding process
-pub async fn scaffold_project(package_name: Option<String>) -> Result<()> {
+pub async fn scaffold_project(package_name: Option<String>, options: NewOptions) -> Result<()> {
+    let user_config = UserConfig::load()?;
+
+    // Validate the template and output location before asking any questions
+    let (template, manifest) = load_template(options.template_path, &user_config)?;
+    let location = match (options.path, options.output_dir) {
+        (Some(path), _) => ProjectLocation::Exact(expand_path(&path)?),
+        (None, Some(output_dir)) => ProjectLocation::Under(expand_path(&output_dir)?),
+        (None, None) => match user_config.path_value("output_dir")? {
+            Some(output_dir) => ProjectLocation::Under(output_dir),
+            None => ProjectLocation::Under(PathBuf::new()),
+        },
+    };
+    match &location {
+        ProjectLocation::Exact(path) => {
+            validate_project_directory(path)?;
+            validate_parent_directory(path.parent().unwrap_or(Path::new("")))?;
+        }
+        ProjectLocation::Under(output_dir) => validate_parent_directory(output_dir)?,
+    }
+
+    let keystore = keystore_dir(options.keypairs.keystore, &user_config)?;
+
+    // The project directory is checked before the keypairs are generated, so
+    // a vanity grind is never thrown away because the directory exists
+    let package_name = collect_program_name(package_name, &options.answers)?;
+    let project_dir = match location {
+        ProjectLocation::Exact(path) => path,
+        ProjectLocation::Under(output_dir) => output_dir.join(&package_name),
+    };
+    validate_project_directory(&project_dir)?;
+
     // Build project configuration from user input
-    let config = ProjectConfig::build(package_name).await?;
+    let config = ProjectConfig::build(
+        Some(package_name),
+        &manifest,
+        &options.answers,
+        &user_config,
+        &options.keypairs,
+        keystore.as_deref(),
+    )
+    .await?;
 
     println!(
         "{} Creating new Pinocchio project: {}",
@@ -33,33 +154,43 @@ pub async fn scaffold_project(package_name: Option<String>) -> Result<()> {
         style(&config.program_name_dash).cyan().bold()
     );
 
-    // Validate project and template directories
-    validate_project_directory(&config.program_name_dash)?;
-    let template_dir = Path::new("template");
-    validate_template_directory(template_dir)?;
+    print_template_source(&template);
 
-    // Create project directory
-    let project_dir = Path::new(&config.program_name_dash);
-    fs::create_dir_all(project_dir)?;
+    // Again, in case it was created while the keypairs were generated
+    validate_project_directory(&project_dir)?;
 
-    // Setup templating
-    let mut tera = Tera::default();
-    let context = create_template_context(&config);
+    // Render every template file in memory before touching the disk
+    let files = render_project_files(&template, &manifest, &config)?;
 
-    // Copy template files with processing
-    copy_template_files(
-        template_dir,
-        project_dir,
-        &mut tera,
-        &context,
-        &config.program_name_dash,
-    )?;
+    if options.dry_run {
+        print_dry_run(
+            &files,
+            &template,
+            &project_dir,
+            options.show_diff,
+            &config.keypair_files(),
+        )?;
+        return Ok(());
+    }
 
-    // Create program-id.json
-    create_program_id_file(project_dir, &config.program_keypair)?;
+    // Generate into a staging directory, removed again on error or Ctrl-C
+    let staging = StagingDir::create(&project_dir)?;
+    staging.remove_on_interrupt();
+    write_rendered_files(&files, staging.path())?;
+    create_keypair_files(staging.path(), &config)?;
+    // Keystore keypairs are written before the project is moved into place, so
+    // a failure rolls it back instead of leaving it pointing at a missing file
+    store_keypairs(&config)?;
+
+    // Move the complete project into place
+    staging.commit()?;
 
     // Print success message
-    print_success_message(&config.program_pubkey);
+    print_success_message(&config.program_pubkey, config.deployer_pubkey.as_deref());
+
+    if !options.no_hooks {
+        run_post_generation(&project_dir, &manifest, &create_template_context(&config))?;
+    }
 
     Ok(())
 }
@@ -1,8 +1,60 @@
 use anyhow::Result;
 use chrono::Datelike;
+use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, Signer};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use crate::io::collect_user_input;
+use crate::answers::Answers;
+use crate::io::{collect_license, collect_template_variables, collect_user_input};
+use crate::keypair::{DEPLOYER_FILE, PROGRAM_ID_FILE};
+use crate::license::License;
+use crate::manifest::TemplateManifest;
+use crate::user_config::UserConfig;
+use crate::vanity::{grind_keypair, VanityPattern};
+
+/// How the program keypair is obtained
+#[derive(Debug, Default)]
+pub enum ProgramKeypairSource {
+    /// A fresh random keypair
+    #[default]
+    Generate,
+    /// Random keypairs until the program ID matches the pattern
+    Vanity {
+        pattern: VanityPattern,
+        timeout: Option<Duration>,
+    },
+    /// An existing keypair, e.g. of a program that is already deployed
+    Existing(Keypair),
+    /// Only the program ID, without a keypair to write
+    ProgramId(Pubkey),
+}
+
+/// How the deployer keypair, also the upgrade authority, is obtained
+#[derive(Debug, Default)]
+pub enum DeployerSource {
+    /// No deployer, the Solana CLI's default keypair is used
+    #[default]
+    None,
+    /// A fresh random keypair written as `deployer.json`
+    Generate,
+    /// An existing keypair copied into the project as `deployer.json`
+    Import(Keypair),
+    /// An existing keypair file used where it is
+    Reference { path: PathBuf, pubkey: Pubkey },
+}
+
+/// How the keypairs of a new project are obtained and where they are kept
+#[derive(Debug, Default)]
+pub struct KeypairOptions {
+    /// How to obtain the program keypair
+    pub program: ProgramKeypairSource,
+    /// How to obtain the deployer keypair
+    pub deployer: DeployerSource,
+    /// Keep generated and imported keypairs in the user keystore
+    pub keystore: bool,
+}
 
 /// Represents the project configuration data
 #[derive(Debug)]
@@ -11,9 +63,26 @@ pub struct ProjectConfig {
     pub program_name_underscore: String,
     pub program_name_readable: String,
     pub company_name: String,
+    /// Author email from the user config, empty if unset
+    pub author_email: String,
     pub year: i32,
+    pub license: License,
+    /// Whether generated Rust files start with an SPDX license header
+    pub spdx_headers: bool,
     pub program_pubkey: String,
-    pub program_keypair: Keypair,
+    /// None when only the program ID was given
+    pub program_keypair: Option<Keypair>,
+    /// Where the project finds the program keypair: `program-id.json` in the
+    /// project root, or a file in the keystore. None without a keypair
+    pub program_keypair_path: Option<PathBuf>,
+    /// Public key of the deployer and upgrade authority, if any
+    pub deployer_pubkey: Option<String>,
+    /// Deployer keypair to write, None when it is referenced or absent
+    pub deployer_keypair: Option<Keypair>,
+    /// Where the project finds the deployer keypair, like `program_keypair_path`
+    pub deployer_keypair_path: Option<PathBuf>,
+    /// Values of the variables declared in the template manifest
+    pub variables: BTreeMap<String, serde_json::Value>,
 }
 
 impl ProjectConfig {
@@ -21,16 +90,54 @@ impl ProjectConfig {
     ///
     /// # Arguments
     /// * `package_name` - Optional package name, will prompt if None
+    /// * `manifest` - Template manifest declaring additional variables
+    /// * `answers` - Answers given ahead of time, not prompted for
+    /// * `user_config` - User defaults prefilling the prompts
+    /// * `keypairs` - How to obtain the program and deployer keypairs
+    /// * `keystore` - Directory to keep keypairs in, None to keep them in the project
     ///
     /// # Returns
     /// A ProjectConfig struct containing all necessary project data
-    pub async fn build(package_name: Option<String>) -> Result<Self> {
-        let (program_name_dash, company_name) = collect_user_input(package_name)?;
+    pub async fn build(
+        package_name: Option<String>,
+        manifest: &TemplateManifest,
+        answers: &Answers,
+        user_config: &UserConfig,
+        keypairs: &KeypairOptions,
+        keystore: Option<&Path>,
+    ) -> Result<Self> {
+        let (program_name_dash, company_name) =
+            collect_user_input(package_name, answers, user_config)?;
+        let license = collect_license(answers, user_config)?;
+        let variables = collect_template_variables(manifest, answers)?;
         let (program_name_underscore, program_name_readable) =
             Self::generate_program_name_variants(&program_name_dash);
 
-        let program_keypair = Self::generate_program_keypair();
-        let program_pubkey = program_keypair.pubkey().to_string();
+        let (program_pubkey, program_keypair) = match &keypairs.program {
+            ProgramKeypairSource::ProgramId(program_id) => (program_id.to_string(), None),
+            source => {
+                let keypair = Self::generate_program_keypair(source)?;
+                (keypair.pubkey().to_string(), Some(keypair))
+            }
+        };
+        let keypair_path = |pubkey: &str, file_name: &str| match keystore {
+            Some(dir) => dir.join(format!("{}.json", pubkey)),
+            None => PathBuf::from(file_name),
+        };
+        let program_keypair_path = program_keypair
+            .as_ref()
+            .map(|_| keypair_path(&program_pubkey, PROGRAM_ID_FILE));
+
+        let (deployer_pubkey, deployer_keypair, deployer_keypair_path) = match &keypairs.deployer {
+            DeployerSource::None => (None, None, None),
+            DeployerSource::Reference { path, pubkey } => {
+                (Some(pubkey.to_string()), None, Some(path.clone()))
+            }
+            DeployerSource::Generate => Self::deployer_to_write(Keypair::new(), keypair_path),
+            DeployerSource::Import(keypair) => {
+                Self::deployer_to_write(keypair.insecure_clone(), keypair_path)
+            }
+        };
         let year = Self::get_current_year();
 
         Ok(ProjectConfig {
@@ -38,12 +145,44 @@ impl ProjectConfig {
             program_name_underscore,
             program_name_readable,
             company_name,
+            author_email: user_config.author_email.clone().unwrap_or_default(),
             year,
+            license,
+            spdx_headers: answers.spdx_headers,
             program_pubkey,
             program_keypair,
+            program_keypair_path,
+            deployer_pubkey,
+            deployer_keypair,
+            deployer_keypair_path,
+            variables,
         })
     }
 
+    /// Pubkey, keypair and path of a deployer keypair gepetto writes
+    fn deployer_to_write(
+        keypair: Keypair,
+        keypair_path: impl Fn(&str, &str) -> PathBuf,
+    ) -> (Option<String>, Option<Keypair>, Option<PathBuf>) {
+        let pubkey = keypair.pubkey().to_string();
+        let path = keypair_path(&pubkey, DEPLOYER_FILE);
+        (Some(pubkey), Some(keypair), Some(path))
+    }
+
+    /// Keypairs gepetto writes, with the path the project refers to them by
+    ///
+    /// Paths of keypairs kept in the project are relative to its root, paths
+    /// into the keystore are absolute.
+    pub fn keypair_files(&self) -> Vec<(&Path, &Keypair)> {
+        [
+            (&self.program_keypair_path, &self.program_keypair),
+            (&self.deployer_keypair_path, &self.deployer_keypair),
+        ]
+        .into_iter()
+        .filter_map(|(path, keypair)| Some((path.as_deref()?, keypair.as_ref()?)))
+        .collect()
+    }
+
     /// Generates derived program name formats from the dash-separated name
     ///
     /// # Arguments
@@ -69,12 +208,23 @@ impl ProjectConfig {
         (program_name_underscore, program_name_readable)
     }
 
-    /// Creates a new Solana program keypair
+    /// Creates or loads the Solana program keypair
+    ///
+    /// # Arguments
+    /// * `source` - Whether to generate, grind for or reuse the keypair
     ///
     /// # Returns
-    /// A new randomly generated keypair
-    fn generate_program_keypair() -> Keypair {
-        Keypair::new()
+    /// The program keypair
+    fn generate_program_keypair(source: &ProgramKeypairSource) -> Result<Keypair> {
+        match source {
+            ProgramKeypairSource::Generate => Ok(Keypair::new()),
+            ProgramKeypairSource::Vanity { pattern, timeout } => grind_keypair(pattern, *timeout),
+            ProgramKeypairSource::Existing(keypair) => Ok(keypair.insecure_clone()),
+            ProgramKeypairSource::ProgramId(program_id) => Err(anyhow::anyhow!(
+                "Program ID {} was given without its keypair",
+                program_id
+            )),
+        }
     }
 
     /// Gets the current year as an integer
@@ -0,0 +1,137 @@
+use heck::{
+    ToKebabCase, ToLowerCamelCase, ToShoutySnakeCase, ToSnakeCase, ToTitleCase, ToUpperCamelCase,
+};
+use solana_sdk::bs58;
+use solana_sdk::hash::hashv;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tera::{Tera, Value};
+
+/// Registers gepetto's filters on a Tera instance
+///
+/// Case conversions let a template derive every identifier from a single
+/// name, e.g. `{{ program_name_dash | pascal_case }}`. The Solana filters
+/// compute values that would otherwise have to be hard-coded in templates.
+///
+/// # Arguments
+/// * `tera` - The Tera instance to register the filters on
+pub fn register_filters(tera: &mut Tera) {
+    tera.register_filter("snake_case", case_filter(|s| s.to_snake_case()));
+    tera.register_filter("kebab_case", case_filter(|s| s.to_kebab_case()));
+    tera.register_filter("pascal_case", case_filter(|s| s.to_upper_camel_case()));
+    tera.register_filter("camel_case", case_filter(|s| s.to_lower_camel_case()));
+    tera.register_filter(
+        "screaming_snake_case",
+        case_filter(|s| s.to_shouty_snake_case()),
+    );
+    tera.register_filter("title_case", case_filter(|s| s.to_title_case()));
+    tera.register_filter("base58", base58);
+    tera.register_filter("pda", pda);
+    tera.register_filter("discriminator", discriminator);
+    tera.register_filter("lamports_for_rent", lamports_for_rent);
+}
+
+/// Wraps a string conversion into a Tera filter
+fn case_filter(
+    convert: fn(&str) -> String,
+) -> impl Fn(&Value, &HashMap<String, Value>) -> tera::Result<Value> {
+    move |value, _args| {
+        let input = value
+            .as_str()
+            .ok_or_else(|| tera::Error::msg("case filters expect a string"))?;
+        Ok(Value::from(convert(input)))
+    }
+}
+
+/// Encodes a string (as UTF-8) or an array of bytes as base58
+///
+/// `{{ [1, 2, 3] | base58 }}` renders `Ldp`.
+fn base58(value: &Value, _args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let bytes = value_to_bytes(value, "base58")?;
+    Ok(Value::from(bs58::encode(bytes).into_string()))
+}
+
+/// Derives a program derived address from a program ID
+///
+/// `{{ program_pubkey | pda(seeds=["counter", authority]) }}` renders the
+/// address; with `bump=true` it renders the bump seed instead. String seeds
+/// that are valid public keys are used as 32-byte keys, other strings as
+/// UTF-8 bytes, and arrays as raw bytes.
+fn pda(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let program_id = value
+        .as_str()
+        .and_then(|s| Pubkey::from_str(s).ok())
+        .ok_or_else(|| tera::Error::msg("pda expects a program ID in base58"))?;
+    let seeds = args
+        .get("seeds")
+        .and_then(Value::as_array)
+        .ok_or_else(|| tera::Error::msg("pda expects a `seeds` array argument"))?;
+
+    let seeds = seeds
+        .iter()
+        .map(|seed| match seed.as_str().map(Pubkey::from_str) {
+            Some(Ok(pubkey)) => Ok(pubkey.to_bytes().to_vec()),
+            _ => value_to_bytes(seed, "pda"),
+        })
+        .collect::<tera::Result<Vec<_>>>()?;
+    if seeds.iter().any(|seed| seed.len() > 32) {
+        return Err(tera::Error::msg("pda seeds must be at most 32 bytes long"));
+    }
+    let seed_refs = seeds.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    let (address, bump) = Pubkey::find_program_address(&seed_refs, &program_id);
+
+    match args.get("bump").and_then(Value::as_bool) {
+        Some(true) => Ok(Value::from(bump)),
+        _ => Ok(Value::from(address.to_string())),
+    }
+}
+
+/// Computes an Anchor-compatible 8-byte discriminator
+///
+/// `{{ "increase" | discriminator }}` hashes `global:increase`; pass
+/// `namespace="account"` for account discriminators. The result is an array
+/// of bytes, e.g. for `{{ ... | join(sep=", ") }}`.
+fn discriminator(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let name = value
+        .as_str()
+        .ok_or_else(|| tera::Error::msg("discriminator expects a string"))?;
+    let namespace = args
+        .get("namespace")
+        .and_then(Value::as_str)
+        .unwrap_or("global");
+    let hash = hashv(&[namespace.as_bytes(), b":", name.as_bytes()]);
+    Ok(Value::from(hash.to_bytes()[..8].to_vec()))
+}
+
+/// Computes the rent-exempt minimum balance for an account of the given size
+///
+/// `{{ 41 | lamports_for_rent }}` uses the default rent parameters.
+fn lamports_for_rent(value: &Value, _args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let len = value
+        .as_u64()
+        .ok_or_else(|| tera::Error::msg("lamports_for_rent expects an account size in bytes"))?;
+    Ok(Value::from(Rent::default().minimum_balance(len as usize)))
+}
+
+/// Reads a filter input as bytes: strings as UTF-8, arrays as raw bytes
+fn value_to_bytes(value: &Value, filter: &str) -> tera::Result<Vec<u8>> {
+    match value {
+        Value::String(s) => Ok(s.as_bytes().to_vec()),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| {
+                item.as_u64()
+                    .and_then(|byte| u8::try_from(byte).ok())
+                    .ok_or_else(|| {
+                        tera::Error::msg(format!("{} expects an array of bytes", filter))
+                    })
+            })
+            .collect(),
+        _ => Err(tera::Error::msg(format!(
+            "{} expects a string or an array of bytes",
+            filter
+        ))),
+    }
+}
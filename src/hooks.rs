@@ -0,0 +1,273 @@
+use anyhow::Result;
+use console::style;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::time::Duration;
+use tera::{Context, Tera};
+
+use crate::filters::register_filters;
+use crate::manifest::TemplateManifest;
+
+/// Contents of the `.gitignore` written when the template does not ship one,
+/// keeping build output and keypairs out of the initial commit
+const DEFAULT_GITIGNORE: &str = "target/\nprogram-id.json\ndeployer.json\n*keypair*.json\n";
+
+/// Name hook commands are rendered under, with escaping turned on
+const HOOK_TEMPLATE_NAME: &str = "hook.sh";
+
+/// Private-use characters around a value while a hook command is split
+const VALUE_START: char = '\u{E000}';
+const VALUE_END: char = '\u{E001}';
+
+/// Message of the commit created by the built-in git hook
+const INITIAL_COMMIT_MESSAGE: &str = "Initial commit from gepetto";
+
+/// Runs the post-generation phase in a freshly generated project
+///
+/// Commands declared by the template run first, so that e.g. `cargo fmt`
+/// output ends up in the initial commit. They run without a shell: the
+/// rendered command is split into words the way a POSIX shell would, with
+/// every value rendered into it kept out of the split, so a value such as a
+/// company name always stays within its word and can never run commands.
+/// The built-in git step then runs `git init`, adds a `.gitignore` if there
+/// is none and commits everything, unless the project already lives inside a
+/// git work tree.
+///
+/// # Arguments
+/// * `project_dir` - Path to the generated project
+/// * `manifest` - Template manifest declaring the hooks
+/// * `context` - Template context, used to render hook commands
+///
+/// # Returns
+/// Result indicating success, or the first step that failed
+pub fn run_post_generation(
+    project_dir: &Path,
+    manifest: &TemplateManifest,
+    context: &Context,
+) -> Result<()> {
+    let mut tera = hook_tera();
+    for command in &manifest.hooks.post_generate {
+        let words = render_hook(&mut tera, command, context)?;
+        let label =
+            shlex::try_join(words.iter().map(String::as_str)).unwrap_or_else(|_| words.join(" "));
+        run_step(&label, || {
+            Command::new(&words[0])
+                .args(&words[1..])
+                .current_dir(project_dir)
+                .output()
+        })?;
+    }
+
+    if manifest.hooks.git_init {
+        run_git_init(project_dir)?;
+    }
+
+    Ok(())
+}
+
+/// Tera instance rendering hook commands, escaping every value with `hide_value`
+fn hook_tera() -> Tera {
+    let mut tera = Tera::default();
+    register_filters(&mut tera);
+    tera.autoescape_on(vec![HOOK_TEMPLATE_NAME]);
+    tera.set_escape_fn(hide_value);
+    tera
+}
+
+/// Renders a hook command and splits it into the program and its arguments
+///
+/// # Arguments
+/// * `tera` - Tera instance from `hook_tera`
+/// * `command` - Hook command as declared in the manifest
+/// * `context` - Template context
+///
+/// # Returns
+/// The words of the command, or Err if it does not render or is badly quoted
+fn render_hook(tera: &mut Tera, command: &str, context: &Context) -> Result<Vec<String>> {
+    let rendered = tera
+        .add_raw_template(HOOK_TEMPLATE_NAME, command)
+        .and_then(|_| tera.render(HOOK_TEMPLATE_NAME, context))
+        .map_err(|e| anyhow::anyhow!("Failed to render hook '{}': {}", command, e))?;
+    let words = shlex::split(&rendered)
+        .filter(|words| !words.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("Invalid hook '{}', check its quoting", command))?;
+    Ok(words.iter().map(|word| reveal_values(word)).collect())
+}
+
+/// Initializes a git repository with an initial commit
+fn run_git_init(project_dir: &Path) -> Result<()> {
+    let Ok(inside) = git(&["rev-parse", "--is-inside-work-tree"], project_dir) else {
+        println!(
+            "{} git not found, skipping repository initialization",
+            style("⚠️").yellow()
+        );
+        return Ok(());
+    };
+    if inside.status.success() {
+        println!(
+            "{} Already inside a git repository, skipping git init",
+            style("ℹ️").blue()
+        );
+        return Ok(());
+    }
+
+    run_step("git init", || git(&["init", "--quiet"], project_dir))?;
+
+    let gitignore = project_dir.join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(&gitignore, DEFAULT_GITIGNORE)?;
+    }
+
+    run_step("git add", || git(&["add", "--all"], project_dir))?;
+
+    // The repository is usable without the commit, e.g. when no git identity
+    // is configured yet, so a failed commit is only reported
+    if let Err(e) = run_step("git commit", || {
+        git(
+            &["commit", "--quiet", "-m", INITIAL_COMMIT_MESSAGE],
+            project_dir,
+        )
+    }) {
+        println!("{} {}", style("⚠️").yellow(), e);
+    }
+    Ok(())
+}
+
+/// Runs a single step behind a spinner and reports how it went
+///
+/// # Arguments
+/// * `label` - What is shown to the user while the step runs
+/// * `step` - Runs the step and returns the process output
+///
+/// # Returns
+/// Ok(()) if the process succeeded, Err with its stderr otherwise
+fn run_step(label: &str, step: impl FnOnce() -> std::io::Result<Output>) -> Result<()> {
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(ProgressStyle::with_template("{spinner:.cyan} {msg}")?);
+    spinner.set_message(label.to_string());
+    spinner.enable_steady_tick(Duration::from_millis(80));
+
+    let result = step();
+    spinner.finish_and_clear();
+
+    let output = result.map_err(|e| anyhow::anyhow!("Failed to run '{}': {}", label, e))?;
+    if !output.status.success() {
+        println!("{} {}", style("❌").red(), label);
+        return Err(anyhow::anyhow!(
+            "Post-generation step '{}' failed ({}):\n{}",
+            label,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        ));
+    }
+
+    println!("{} {}", style("✔").green(), label);
+    Ok(())
+}
+
+fn git(args: &[&str], dir: &Path) -> std::io::Result<Output> {
+    Command::new("git").args(args).current_dir(dir).output()
+}
+
+/// Escape function of hook commands, hiding a value from the word split
+///
+/// The value is hex encoded between two private-use characters, none of
+/// which a shell treats specially, and restored by `reveal_values`.
+fn hide_value(value: &str) -> String {
+    let hex = value
+        .bytes()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    format!("{}{}{}", VALUE_START, hex, VALUE_END)
+}
+
+/// Restores the values hidden by `hide_value` in a word of a hook command
+fn reveal_values(word: &str) -> String {
+    let mut revealed = String::new();
+    let mut rest = word;
+    while let Some(start) = rest.find(VALUE_START) {
+        let hex_start = start + VALUE_START.len_utf8();
+        let Some(len) = rest[hex_start..].find(VALUE_END) else {
+            break;
+        };
+        let hex = &rest[hex_start..hex_start + len];
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .filter_map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect::<Vec<_>>();
+        revealed.push_str(&rest[..start]);
+        revealed.push_str(&String::from_utf8_lossy(&bytes));
+        rest = &rest[hex_start + len + VALUE_END.len_utf8()..];
+    }
+    revealed.push_str(rest);
+    revealed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPANY: &str = "Acme \"Co\" 'Ltd'; rm -rf ~ && echo $HOME `id` | Société ünï 日本";
+
+    fn words(command: &str) -> Result<Vec<String>> {
+        let mut context = Context::new();
+        context.insert("company_name", COMPANY);
+        context.insert("program_name_dash", "my-counter");
+        render_hook(&mut hook_tera(), command, &context)
+    }
+
+    #[test]
+    fn test_values_stay_in_their_word() {
+        assert_eq!(words("echo {{ company_name }}").unwrap(), ["echo", COMPANY]);
+        assert_eq!(
+            words("echo \"By {{ company_name }}!\" '{{ program_name_dash }}'").unwrap(),
+            [
+                "echo".to_string(),
+                format!("By {}!", COMPANY),
+                "my-counter".to_string()
+            ]
+        );
+        assert_eq!(
+            words("touch {{ program_name_dash }}-{{ company_name }}.txt").unwrap(),
+            ["touch".to_string(), format!("my-counter-{}.txt", COMPANY)]
+        );
+    }
+
+    #[test]
+    fn test_template_quoting_still_applies() {
+        assert_eq!(
+            words("cargo   fmt --all ; echo 'a b'").unwrap(),
+            ["cargo", "fmt", "--all", ";", "echo", "a b"]
+        );
+    }
+
+    #[test]
+    fn test_bad_hooks_are_rejected() {
+        for command in [
+            "echo 'unterminated",
+            "echo \"{{ company_name }}",
+            "echo \\",
+            "",
+            "   ",
+        ] {
+            let error = words(command).unwrap_err().to_string();
+            assert!(
+                error.contains("check its quoting"),
+                "{}: {}",
+                command,
+                error
+            );
+        }
+        assert!(words("echo {{ missing }}").is_err());
+    }
+
+    #[test]
+    fn test_hide_and_reveal_values() {
+        for value in ["", "plain", COMPANY, "\u{E000}\u{E001}"] {
+            assert_eq!(reveal_values(&hide_value(value)), value);
+            assert!(!hide_value(value).contains(|c: char| c.is_whitespace() || c == '\''));
+        }
+    }
+}
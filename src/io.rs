@@ -1,6 +1,18 @@
 use anyhow::Result;
 use console::style;
-use dialoguer::Input;
+use dialoguer::{Confirm, Input, Select};
+use similar::{ChangeTag, TextDiff};
+use solana_sdk::signature::{Keypair, Signer};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::answers::Answers;
+use crate::commands::init::ConflictPolicy;
+use crate::license::License;
+use crate::manifest::{TemplateManifest, VariableKind};
+use crate::template::{FileAction, RenderedFile, TemplateSource};
+use crate::user_config::UserConfig;
+use crate::validation::{suggest_program_name, validate_program_name};
 
 /// Prints the welcome message when no command is provided
 pub fn print_welcome_message() {
@@ -10,35 +22,340 @@ pub fn print_welcome_message() {
 
 /// Collects user input for project configuration
 ///
+/// Values already given in `answers` are not asked for, and prompts are
+/// prefilled with the user's defaults.
+///
 /// # Arguments
 /// * `package_name` - Optional package name, will prompt if None
+/// * `answers` - Answers given with flags, env vars or an answers file
+/// * `user_config` - Defaults from the user config file
 ///
 /// # Returns
 /// A tuple containing (program_name_dash, company_name)
-pub fn collect_user_input(package_name: Option<String>) -> Result<(String, String)> {
-    let program_name_dash = match package_name {
-        Some(name) => name,
-        None => Input::<String>::new()
-            .with_prompt("Program name (e.g., some-counter)")
-            .interact()?,
+pub fn collect_user_input(
+    package_name: Option<String>,
+    answers: &Answers,
+    user_config: &UserConfig,
+) -> Result<(String, String)> {
+    let program_name_dash = collect_program_name(package_name, answers)?;
+
+    let company_name =
+        match (&answers.company, &user_config.company) {
+            (Some(company), _) => company.clone(),
+            (None, Some(company)) if !answers.interactive => company.clone(),
+            (None, None) if !answers.interactive => return Err(Answers::missing(
+                "company name",
+                "pass --company, set GEPETTO_COMPANY or run `gepetto config set company <name>`",
+            )),
+            (None, default) => {
+                let mut input = Input::<String>::new().with_prompt("Company name");
+                if let Some(default) = default {
+                    input = input.default(default.clone());
+                }
+                input.interact()?
+            }
+        };
+
+    Ok((program_name_dash, company_name))
+}
+
+/// Collects the program name, asking again while it is invalid
+///
+/// # Arguments
+/// * `package_name` - Optional package name, will prompt if None
+/// * `answers` - Answers given with flags, env vars or an answers file
+///
+/// # Returns
+/// A valid dash-separated program name
+pub fn collect_program_name(package_name: Option<String>, answers: &Answers) -> Result<String> {
+    let program_name_dash = match package_name.or_else(|| answers.name.clone()) {
+        Some(name) if validate_program_name(&name).is_ok() => name,
+        Some(name) if !answers.interactive => {
+            validate_program_name(&name)?;
+            name
+        }
+        None if !answers.interactive => {
+            return Err(Answers::missing(
+                "program name",
+                "pass it as an argument or set GEPETTO_NAME",
+            ))
+        }
+        // An invalid name is reported and asked for again, with the suggested
+        // correction as the default
+        name => {
+            let mut input = Input::<String>::new().with_prompt("Program name (e.g., some-counter)");
+            if let Some(name) = name {
+                if let Err(e) = validate_program_name(&name) {
+                    println!("{} {}", style("⚠️").yellow(), e);
+                }
+                if let Some(suggestion) = suggest_program_name(&name) {
+                    input = input.default(suggestion);
+                }
+            }
+            input
+                .validate_with(|name: &String| {
+                    validate_program_name(name).map_err(|e| e.to_string())
+                })
+                .interact_text()?
+        }
     };
+    Ok(program_name_dash)
+}
 
-    let company_name = Input::<String>::new()
-        .with_prompt("Company name")
+/// Chooses the project license
+///
+/// An answered license is used as is. Otherwise the user's default license,
+/// or MIT, is preselected in the prompt, or used directly without prompts.
+///
+/// # Arguments
+/// * `answers` - Answers given with flags, env vars or an answers file
+/// * `user_config` - Defaults from the user config file
+///
+/// # Returns
+/// The chosen license
+pub fn collect_license(answers: &Answers, user_config: &UserConfig) -> Result<License> {
+    if let Some(license) = answers.license {
+        return Ok(license);
+    }
+    let default = match &user_config.license {
+        Some(license) => license
+            .parse::<License>()
+            .map_err(|e| anyhow::anyhow!("Invalid license in the user config: {}", e))?,
+        None => License::default(),
+    };
+    if !answers.interactive {
+        return Ok(default);
+    }
+
+    let items = License::ALL
+        .iter()
+        .map(|license| license.describe())
+        .collect::<Vec<_>>();
+    let index = Select::new()
+        .with_prompt("License")
+        .items(&items)
+        .default(License::ALL.iter().position(|l| *l == default).unwrap_or(0))
         .interact()?;
+    Ok(License::ALL[index])
+}
 
-    Ok((program_name_dash, company_name))
+/// Prompts for every variable declared in the template manifest
+///
+/// Answered variables are checked like typed ones and not asked for. Without
+/// prompts, unanswered variables fall back to their default.
+///
+/// # Arguments
+/// * `manifest` - The template manifest
+/// * `answers` - Answers given with flags, env vars or an answers file
+///
+/// # Returns
+/// A map from variable name to its typed value
+pub fn collect_template_variables(
+    manifest: &TemplateManifest,
+    answers: &Answers,
+) -> Result<BTreeMap<String, serde_json::Value>> {
+    if let Some(unknown) = answers
+        .variable_names()
+        .find(|name| !manifest.variables.iter().any(|v| &v.name == *name))
+    {
+        let declared = manifest
+            .variables
+            .iter()
+            .map(|v| v.name.as_str())
+            .collect::<Vec<_>>();
+        return Err(match declared.is_empty() {
+            true => anyhow::anyhow!(
+                "Unknown template variable '{}', the template declares no variables",
+                unknown
+            ),
+            false => anyhow::anyhow!(
+                "Unknown template variable '{}', the template declares: {}",
+                unknown,
+                declared.join(", ")
+            ),
+        });
+    }
+
+    let mut values = BTreeMap::new();
+
+    for variable in &manifest.variables {
+        let answer = if let Some(answer) = answers.variable(&variable.name) {
+            answer
+        } else if !answers.interactive {
+            match &variable.default {
+                Some(default) => default.clone(),
+                None if variable.kind == VariableKind::Bool => false.to_string(),
+                None => {
+                    return Err(Answers::missing(
+                        &format!("value for template variable '{}'", variable.name),
+                        &format!(
+                            "pass --var {}=<value> or set GEPETTO_VAR_{}",
+                            variable.name,
+                            variable.name.to_uppercase()
+                        ),
+                    ))
+                }
+            }
+        } else if variable.kind == VariableKind::Bool {
+            let default = match &variable.default {
+                Some(default) => default.parse::<bool>()?,
+                None => false,
+            };
+            Confirm::new()
+                .with_prompt(&variable.prompt)
+                .default(default)
+                .interact()?
+                .to_string()
+        } else if !variable.choices.is_empty() {
+            let default = variable
+                .default
+                .as_ref()
+                .and_then(|d| variable.choices.iter().position(|c| c == d))
+                .unwrap_or(0);
+            let index = Select::new()
+                .with_prompt(&variable.prompt)
+                .items(&variable.choices)
+                .default(default)
+                .interact()?;
+            variable.choices[index].clone()
+        } else {
+            let mut input = Input::<String>::new().with_prompt(&variable.prompt);
+            if let Some(default) = &variable.default {
+                input = input.default(default.clone());
+            }
+            input
+                .validate_with(|value: &String| variable.check(value).map_err(|e| e.to_string()))
+                .interact_text()?
+        };
+
+        values.insert(variable.name.clone(), variable.to_value(&answer)?);
+    }
+
+    Ok(values)
 }
 
 /// Prints success messages after project creation
 ///
 /// # Arguments
 /// * `program_pubkey` - Public key of the generated program
-pub fn print_success_message(program_pubkey: &str) {
+/// * `deployer_pubkey` - Public key of the deployer, if the project has one
+pub fn print_success_message(program_pubkey: &str, deployer_pubkey: Option<&str>) {
     println!("{} Project created successfully!", style("✅").green());
     println!(
         "{} Program ID: {}",
         style("🔑").yellow(),
         style(program_pubkey).cyan()
     );
+    if let Some(deployer_pubkey) = deployer_pubkey {
+        println!(
+            "{} Deployer: {}",
+            style("🚀").yellow(),
+            style(deployer_pubkey).cyan()
+        );
+    }
+}
+
+/// Prints what `gepetto new --dry-run` would write
+///
+/// # Arguments
+/// * `files` - Rendered template files
+/// * `source` - Template source, used to diff templated files against
+/// * `project_dir` - Path the project would be created at
+/// * `show_diff` - Whether to print a diff of each templated file
+/// * `keypair_files` - Keypairs that would be written, with their paths
+pub fn print_dry_run(
+    files: &[RenderedFile],
+    source: &TemplateSource,
+    project_dir: &Path,
+    show_diff: bool,
+    keypair_files: &[(&Path, &Keypair)],
+) -> anyhow::Result<()> {
+    println!(
+        "{} Dry run, nothing will be written to {}",
+        style("🔍").blue(),
+        style(project_dir.display()).cyan()
+    );
+
+    for file in files {
+        let label = match file.action {
+            FileAction::Templated => style("templated").green(),
+            FileAction::Verbatim => style("verbatim ").blue(),
+            FileAction::Skipped => style("skipped  ").dim(),
+        };
+        let path = match file.action {
+            FileAction::Skipped => file.template_path.display().to_string(),
+            _ => project_dir.join(&file.output_path).display().to_string(),
+        };
+        println!("  {} {}", label, path);
+
+        if show_diff && file.action == FileAction::Templated {
+            let original =
+                String::from_utf8_lossy(&source.read_file(&file.template_path)?).into_owned();
+            let rendered = String::from_utf8_lossy(&file.contents).into_owned();
+            print_diff(&original, &rendered);
+        }
+    }
+
+    for (path, keypair) in keypair_files {
+        println!(
+            "  {} {} {}",
+            style("generated").yellow(),
+            project_dir.join(path).display(),
+            style(format!("(keypair {}, not written)", keypair.pubkey())).dim()
+        );
+    }
+    Ok(())
+}
+
+/// Asks what to do with a generated file that already exists
+///
+/// # Arguments
+/// * `path` - Path of the file relative to the project root
+/// * `existing` - Current contents of the file
+/// * `generated` - Contents gepetto would write
+///
+/// # Returns
+/// The policy chosen for this file, never `ConflictPolicy::Prompt`
+pub fn prompt_conflict(path: &Path, existing: &[u8], generated: &[u8]) -> Result<ConflictPolicy> {
+    let items = [
+        "Skip (keep existing file)",
+        "Overwrite",
+        "Keep both (write .gepetto-new)",
+        "Show diff",
+    ];
+
+    loop {
+        let choice = Select::new()
+            .with_prompt(format!("{} already exists", path.display()))
+            .items(&items)
+            .default(0)
+            .interact()?;
+        match choice {
+            0 => return Ok(ConflictPolicy::Skip),
+            1 => return Ok(ConflictPolicy::Overwrite),
+            2 => return Ok(ConflictPolicy::KeepBoth),
+            _ => print_diff(
+                &String::from_utf8_lossy(existing),
+                &String::from_utf8_lossy(generated),
+            ),
+        }
+    }
+}
+
+/// Prints the lines that differ between two texts, with some context
+pub fn print_diff(original: &str, rendered: &str) {
+    let diff = TextDiff::from_lines(original, rendered);
+    for group in diff.grouped_ops(1) {
+        for op in group {
+            for change in diff.iter_changes(&op) {
+                let line = change.to_string_lossy();
+                let line = line.trim_end_matches('\n');
+                match change.tag() {
+                    ChangeTag::Delete => println!("      {}", style(format!("-{}", line)).red()),
+                    ChangeTag::Insert => println!("      {}", style(format!("+{}", line)).green()),
+                    ChangeTag::Equal => println!("      {}", style(format!(" {}", line)).dim()),
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,156 @@
+use anyhow::Result;
+use console::style;
+use solana_sdk::bs58;
+use solana_sdk::signature::{Keypair, Signer};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::ProjectConfig;
+use crate::user_config::UserConfig;
+
+/// Name of the program keypair file kept in the project root
+pub const PROGRAM_ID_FILE: &str = "program-id.json";
+
+/// Name of the deployer keypair file kept in the project root
+pub const DEPLOYER_FILE: &str = "deployer.json";
+
+/// Reads a keypair file in the Solana CLI JSON format or as a base58 secret
+///
+/// The JSON format is the array of 64 bytes written by `solana-keygen` and
+/// `program-id.json`; the base58 format is the 64-byte secret exported by
+/// wallets. `~` is expanded in the path.
+///
+/// # Arguments
+/// * `path` - Path of the keypair file
+///
+/// # Returns
+/// The keypair, or Err if the file is unreadable or not a valid keypair
+pub fn read_keypair_file(path: &Path) -> Result<Keypair> {
+    let expanded = shellexpand::tilde(&path.to_string_lossy()).into_owned();
+    let content = fs::read_to_string(&expanded)
+        .map_err(|e| anyhow::anyhow!("Failed to read keypair '{}': {}", path.display(), e))?;
+    let content = content.trim();
+
+    let bytes = if content.starts_with('[') {
+        serde_json::from_str::<Vec<u8>>(content).map_err(|e| {
+            anyhow::anyhow!(
+                "Keypair '{}' is not a JSON array of bytes: {}",
+                path.display(),
+                e
+            )
+        })?
+    } else {
+        bs58::decode(content).into_vec().map_err(|e| {
+            anyhow::anyhow!(
+                "Keypair '{}' is neither a JSON array nor base58: {}",
+                path.display(),
+                e
+            )
+        })?
+    };
+
+    if bytes.len() != 64 {
+        return Err(anyhow::anyhow!(
+            "Keypair '{}' has {} bytes, expected 64",
+            path.display(),
+            bytes.len()
+        ));
+    }
+    Keypair::try_from(bytes.as_slice()).map_err(|_| {
+        anyhow::anyhow!(
+            "Keypair '{}' is invalid, its public key does not match its secret key",
+            path.display()
+        )
+    })
+}
+
+/// Writes a keypair in the Solana CLI JSON format, readable only by the owner
+///
+/// Missing parent directories are created, and an existing file is replaced.
+///
+/// # Arguments
+/// * `path` - Destination of the keypair file
+/// * `keypair` - The keypair to serialize
+///
+/// # Returns
+/// Result indicating success or failure
+pub fn write_keypair_file(path: &Path, keypair: &Keypair) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string(keypair.to_bytes().as_ref())?;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to write keypair '{}': {}", path.display(), e))?;
+
+    // The mode only applies to new files, so tighten replaced ones too
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    std::io::Write::write_all(&mut file, json.as_bytes())?;
+    Ok(())
+}
+
+/// Directory keypairs are stored in instead of the project, if enabled
+///
+/// The directory is made absolute, since the project refers to it by path.
+///
+/// # Arguments
+/// * `enabled` - Whether `--keystore` was passed
+/// * `user_config` - User defaults, whose `keystore` enables it with a custom directory
+///
+/// # Returns
+/// The keystore directory, or None to keep keypairs in the project
+pub fn keystore_dir(enabled: bool, user_config: &UserConfig) -> Result<Option<PathBuf>> {
+    let dir = match user_config.path_value("keystore")? {
+        Some(dir) => dir,
+        None if !enabled => return Ok(None),
+        None => dirs::data_dir()
+            .map(|dir| dir.join("gepetto").join("keystore"))
+            .ok_or_else(|| {
+                anyhow::anyhow!("Cannot locate the user data directory for the keystore")
+            })?,
+    };
+    Ok(Some(std::path::absolute(dir)?))
+}
+
+/// Writes the keypairs kept in the keystore
+///
+/// # Arguments
+/// * `config` - Project configuration with the keypairs and their paths
+///
+/// # Returns
+/// Result indicating success or failure
+pub fn store_keypairs(config: &ProjectConfig) -> Result<()> {
+    for (path, keypair) in config.keypair_files() {
+        if path.is_relative() {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            // Only directories created here are made private, a keystore the
+            // user already set up keeps its permissions
+            let mut builder = fs::DirBuilder::new();
+            builder.recursive(true);
+            #[cfg(unix)]
+            std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+            builder.create(parent).map_err(|e| {
+                anyhow::anyhow!("Failed to create keystore '{}': {}", parent.display(), e)
+            })?;
+        }
+        write_keypair_file(path, keypair)?;
+        println!(
+            "{} Keypair {} stored in {}",
+            style("🔐").blue(),
+            keypair.pubkey(),
+            style(path.display()).cyan()
+        );
+    }
+    Ok(())
+}
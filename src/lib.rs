@@ -1,8 +1,18 @@
+pub mod answers;
+pub mod codegen;
 pub mod commands;
 pub mod config;
+pub mod filters;
+pub mod hooks;
 pub mod io;
+pub mod keypair;
+pub mod license;
+pub mod manifest;
+pub mod staging;
 pub mod template;
+pub mod user_config;
 pub mod validation;
+pub mod vanity;
 
 pub use commands::*;
 pub use config::ProjectConfig;
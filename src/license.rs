@@ -0,0 +1,118 @@
+use anyhow::Result;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::template::{FileAction, RenderedFile};
+
+/// License of a generated project
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum License {
+    #[default]
+    Mit,
+    Apache2,
+    /// MIT or Apache-2.0, at the user's option
+    MitOrApache2,
+    /// Business Source License, turning into GPL-2.0-or-later after four years
+    Busl,
+    /// All rights reserved
+    Proprietary,
+}
+
+impl License {
+    /// Every license, in the order the prompt lists them
+    pub const ALL: &[License] = &[
+        License::Mit,
+        License::Apache2,
+        License::MitOrApache2,
+        License::Busl,
+        License::Proprietary,
+    ];
+
+    /// SPDX expression, used in `Cargo.toml` and file headers
+    pub fn spdx(&self) -> &'static str {
+        match self {
+            License::Mit => "MIT",
+            License::Apache2 => "Apache-2.0",
+            License::MitOrApache2 => "MIT OR Apache-2.0",
+            License::Busl => "BUSL-1.1",
+            License::Proprietary => "LicenseRef-Proprietary",
+        }
+    }
+
+    /// Short name accepted by `--license`, e.g. `dual`
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            License::Mit => "mit",
+            License::Apache2 => "apache",
+            License::MitOrApache2 => "dual",
+            License::Busl => "busl",
+            License::Proprietary => "proprietary",
+        }
+    }
+
+    /// Human readable description, used by the prompt
+    pub fn describe(&self) -> &'static str {
+        match self {
+            License::Mit => "MIT",
+            License::Apache2 => "Apache-2.0",
+            License::MitOrApache2 => "MIT OR Apache-2.0 (dual)",
+            License::Busl => "BUSL-1.1 (Business Source License)",
+            License::Proprietary => "Proprietary (all rights reserved)",
+        }
+    }
+}
+
+impl fmt::Display for License {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.spdx())
+    }
+}
+
+impl FromStr for License {
+    type Err = anyhow::Error;
+
+    /// Parses a short name or SPDX expression, regardless of case
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        License::ALL
+            .iter()
+            .find(|license| {
+                s.eq_ignore_ascii_case(license.short_name())
+                    || s.eq_ignore_ascii_case(license.spdx())
+            })
+            .copied()
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown license '{}', expected one of: {}",
+                    s,
+                    License::ALL
+                        .iter()
+                        .map(|license| license.short_name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
+/// Prepends an SPDX license header to every generated Rust file
+///
+/// Files that already start with an `SPDX-License-Identifier` comment are
+/// left as they are.
+///
+/// # Arguments
+/// * `files` - Rendered files of the project
+/// * `license` - License named in the headers
+pub fn add_spdx_headers(files: &mut [RenderedFile], license: License) {
+    let header = format!("// SPDX-License-Identifier: {}\n\n", license.spdx());
+    for file in files.iter_mut() {
+        let is_rust = file.output_path.extension().is_some_and(|ext| ext == "rs");
+        if file.action == FileAction::Skipped
+            || !is_rust
+            || file.contents.starts_with(b"// SPDX-License-Identifier:")
+        {
+            continue;
+        }
+        file.contents.splice(0..0, header.bytes());
+    }
+}
@@ -1,8 +1,24 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, Args, Parser, Subcommand};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
 
-use gepetto::commands::scaffold_project;
+use gepetto::answers::Answers;
+use gepetto::codegen::{AccountSpec, ArgSpec, FieldSpec, SeedSpec};
+use gepetto::commands::{
+    add_instruction, add_program, add_state, config_get, config_list, config_set, init_project,
+    scaffold_project, AddInstructionOptions, AddProgramOptions, AddStateOptions, ConflictPolicy,
+    InitOptions, NewOptions,
+};
+use gepetto::config::{DeployerSource, KeypairOptions, ProgramKeypairSource};
 use gepetto::io::print_welcome_message;
+use gepetto::keypair::read_keypair_file;
+use gepetto::license::License;
+use gepetto::vanity::VanityPattern;
 
 #[derive(Parser)]
 #[command(name = "gepetto")]
@@ -18,18 +34,396 @@ enum Commands {
     /// Create a new Pinocchio project
     New {
         /// Package name (optional, will prompt if not provided)
+        #[arg(env = "GEPETTO_NAME")]
         name: Option<String>,
+
+        /// Path to a custom template directory (defaults to the built-in template)
+        #[arg(long, env = "GEPETTO_TEMPLATE")]
+        template: Option<PathBuf>,
+
+        /// Directory to create the project in (defaults to ./<name>)
+        #[arg(long, conflicts_with = "output_dir")]
+        path: Option<PathBuf>,
+
+        /// Directory to create the project under, as <output-dir>/<name>
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+
+        /// Show the files that would be generated without writing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// With --dry-run, also show how each templated file is rendered
+        #[arg(long, requires = "dry_run")]
+        diff: bool,
+
+        /// Skip post-generation hooks (git init, initial commit, template commands)
+        #[arg(long)]
+        no_hooks: bool,
+
+        #[command(flatten)]
+        answers: AnswerArgs,
+
+        #[command(flatten)]
+        keypairs: KeypairArgs,
+    },
+    /// Create a Pinocchio project in an existing directory
+    Init {
+        /// Directory to initialize (defaults to the current directory)
+        path: Option<PathBuf>,
+
+        /// Package name (defaults to the directory name)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Path to a custom template directory (defaults to the built-in template)
+        #[arg(long, env = "GEPETTO_TEMPLATE")]
+        template: Option<PathBuf>,
+
+        /// What to do with generated files that already exist
+        #[arg(long, value_enum, default_value_t = ConflictPolicy::Prompt)]
+        conflict: ConflictPolicy,
+
+        /// Skip post-generation hooks (git init, initial commit, template commands)
+        #[arg(long)]
+        no_hooks: bool,
+
+        #[command(flatten)]
+        answers: AnswerArgs,
+
+        #[command(flatten)]
+        keypairs: KeypairArgs,
+    },
+    /// Add to an existing Pinocchio workspace
+    Add {
+        #[command(subcommand)]
+        item: AddItem,
+    },
+    /// Manage user defaults in ~/.config/gepetto/config.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
     },
 }
 
+#[derive(Subcommand)]
+enum AddItem {
+    /// Add a program crate as programs/<name>, with its own program ID
+    Program {
+        /// Program name (optional, will prompt if not provided)
+        name: Option<String>,
+
+        /// Path to a custom template directory (defaults to the built-in template)
+        #[arg(long, env = "GEPETTO_TEMPLATE")]
+        template: Option<PathBuf>,
+
+        /// Directory inside the workspace (defaults to the current directory)
+        #[arg(long)]
+        path: Option<PathBuf>,
+
+        #[command(flatten)]
+        answers: AnswerArgs,
+    },
+    /// Add an instruction to a program, with its entrypoint arm, test and benchmark
+    Instruction {
+        /// Instruction name, e.g. withdraw
+        name: String,
+
+        /// Program to add it to (defaults to the program of the current directory, or the only one)
+        #[arg(long)]
+        program: Option<String>,
+
+        /// Account as NAME[:FLAGS], FLAGS being signer, writable, pda, program or system, comma-separated (repeatable, in order)
+        #[arg(long = "account", value_name = "NAME[:FLAGS]")]
+        accounts: Vec<AccountSpec>,
+
+        /// Argument as NAME:TYPE, TYPE being u8 to u128, i8 to i128, bool or Pubkey (repeatable, in order)
+        #[arg(long = "arg", value_name = "NAME:TYPE")]
+        args: Vec<ArgSpec>,
+
+        /// Directory inside the workspace (defaults to the current directory)
+        #[arg(long)]
+        path: Option<PathBuf>,
+
+        /// Never prompt, fail on missing values instead (implied when stdin is not a terminal)
+        #[arg(long, env = "GEPETTO_NO_INPUT")]
+        no_input: bool,
+    },
+    /// Add a state account type to a program, with its StateKey variant and a layout test
+    State {
+        /// State name, e.g. vault
+        name: String,
+
+        /// Program to add it to (defaults to the program of the current directory, or the only one)
+        #[arg(long)]
+        program: Option<String>,
+
+        /// Field as NAME:TYPE, TYPE being u8 to u128, i8 to i128, bool or Pubkey (repeatable, in order)
+        #[arg(long = "field", value_name = "NAME:TYPE")]
+        fields: Vec<FieldSpec>,
+
+        /// PDA seed, either literal bytes like vault or a parameter as NAME:TYPE (repeatable, in order)
+        #[arg(long = "seed", value_name = "SEED")]
+        seeds: Vec<SeedSpec>,
+
+        /// Directory inside the workspace (defaults to the current directory)
+        #[arg(long)]
+        path: Option<PathBuf>,
+
+        /// Never prompt, fail on missing values instead (implied when stdin is not a terminal)
+        #[arg(long, env = "GEPETTO_NO_INPUT")]
+        no_input: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the value of a key
+    Get {
+        /// company, license, author_email, template, output_dir or keystore
+        key: String,
+    },
+    /// Set a key, or unset it with an empty value
+    Set {
+        /// company, license, author_email, template, output_dir or keystore
+        key: String,
+        value: String,
+    },
+    /// Print every key and its value
+    List,
+}
+
+/// Answers that would otherwise be prompted for
+#[derive(Args)]
+struct AnswerArgs {
+    /// Company name used in the license and Cargo metadata
+    #[arg(long, env = "GEPETTO_COMPANY")]
+    company: Option<String>,
+
+    /// License: mit, apache, dual, busl or proprietary (or an SPDX expression)
+    #[arg(long, env = "GEPETTO_LICENSE")]
+    license: Option<License>,
+
+    /// Start every generated Rust file with an SPDX license header
+    #[arg(long, env = "GEPETTO_SPDX_HEADERS")]
+    spdx_headers: bool,
+
+    /// Value of a template variable, as NAME=VALUE (repeatable, or GEPETTO_VAR_<NAME>)
+    #[arg(long = "var", value_name = "NAME=VALUE")]
+    vars: Vec<String>,
+
+    /// TOML file with answers: name, company, license, spdx_headers and a [variables] table
+    #[arg(long, env = "GEPETTO_ANSWERS")]
+    answers: Option<PathBuf>,
+
+    /// Never prompt, fail on missing values instead (implied when stdin is not a terminal)
+    #[arg(long, env = "GEPETTO_NO_INPUT")]
+    no_input: bool,
+}
+
+impl AnswerArgs {
+    fn into_answers(self) -> Result<Answers> {
+        Answers::new(
+            self.company,
+            self.license,
+            self.spdx_headers,
+            &self.vars,
+            self.answers.as_deref(),
+            self.no_input,
+        )
+    }
+}
+
+/// How the program and deployer keypairs are obtained
+#[derive(Args)]
+#[command(group(ArgGroup::new("vanity").multiple(true)))]
+struct KeypairArgs {
+    /// Grind for a program ID starting with these base58 characters
+    #[arg(long, group = "vanity")]
+    vanity_prefix: Option<String>,
+
+    /// Grind for a program ID ending with these base58 characters
+    #[arg(long, group = "vanity")]
+    vanity_suffix: Option<String>,
+
+    /// Match the vanity prefix and suffix regardless of case
+    #[arg(long, requires = "vanity")]
+    vanity_ignore_case: bool,
+
+    /// Give up grinding after this many seconds
+    #[arg(long, value_name = "SECONDS", requires = "vanity")]
+    vanity_timeout: Option<u64>,
+
+    /// Use an existing program keypair (JSON byte array or base58 secret)
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["vanity", "program_id"])]
+    program_keypair: Option<PathBuf>,
+
+    /// Use an existing program ID without its keypair (no program-id.json is written)
+    #[arg(long, value_name = "PUBKEY", conflicts_with = "vanity")]
+    program_id: Option<String>,
+
+    /// Generate a deployer and upgrade authority keypair as deployer.json
+    #[arg(long, group = "deployer_source")]
+    generate_deployer: bool,
+
+    /// Copy an existing deployer keypair into the project as deployer.json
+    #[arg(long, value_name = "FILE", group = "deployer_source")]
+    import_deployer: Option<PathBuf>,
+
+    /// Use an existing deployer keypair where it is, e.g. ~/.config/solana/id.json
+    #[arg(long, value_name = "FILE", group = "deployer_source")]
+    deployer: Option<PathBuf>,
+
+    /// Keep generated and imported keypairs in the user keystore instead of the project
+    #[arg(long)]
+    keystore: bool,
+}
+
+impl KeypairArgs {
+    fn into_keypair_options(self) -> Result<KeypairOptions> {
+        let deployer = if self.generate_deployer {
+            DeployerSource::Generate
+        } else if let Some(path) = &self.import_deployer {
+            DeployerSource::Import(read_keypair_file(path)?)
+        } else if let Some(path) = &self.deployer {
+            let pubkey = read_keypair_file(path)?.pubkey();
+            let path = std::path::absolute(shellexpand::tilde(&path.to_string_lossy()).as_ref())?;
+            DeployerSource::Reference { path, pubkey }
+        } else {
+            DeployerSource::None
+        };
+
+        Ok(KeypairOptions {
+            keystore: self.keystore,
+            deployer,
+            program: self.into_program_source()?,
+        })
+    }
+
+    fn into_program_source(self) -> Result<ProgramKeypairSource> {
+        if let Some(path) = self.program_keypair {
+            return Ok(ProgramKeypairSource::Existing(read_keypair_file(&path)?));
+        }
+        if let Some(program_id) = self.program_id {
+            let program_id = Pubkey::from_str(&program_id)
+                .map_err(|e| anyhow::anyhow!("Invalid program ID '{}': {}", program_id, e))?;
+            return Ok(ProgramKeypairSource::ProgramId(program_id));
+        }
+        let pattern = VanityPattern::new(
+            self.vanity_prefix,
+            self.vanity_suffix,
+            self.vanity_ignore_case,
+        )?;
+        Ok(match pattern {
+            Some(pattern) => ProgramKeypairSource::Vanity {
+                pattern,
+                timeout: self.vanity_timeout.map(Duration::from_secs),
+            },
+            None => ProgramKeypairSource::Generate,
+        })
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Some(Commands::New { name }) => {
-            scaffold_project(name).await?;
+        Some(Commands::New {
+            name,
+            template,
+            path,
+            output_dir,
+            dry_run,
+            diff,
+            no_hooks,
+            answers,
+            keypairs,
+        }) => {
+            let options = NewOptions {
+                answers: answers.into_answers()?,
+                keypairs: keypairs.into_keypair_options()?,
+                template_path: template,
+                path,
+                output_dir,
+                dry_run,
+                show_diff: diff,
+                no_hooks,
+            };
+            scaffold_project(name, options).await?;
+        }
+        Some(Commands::Init {
+            path,
+            name,
+            template,
+            conflict,
+            no_hooks,
+            answers,
+            keypairs,
+        }) => {
+            let options = InitOptions {
+                answers: answers.into_answers()?,
+                keypairs: keypairs.into_keypair_options()?,
+                template_path: template,
+                conflict_policy: conflict,
+                no_hooks,
+            };
+            init_project(path, name, options).await?;
         }
+        Some(Commands::Add { item }) => match item {
+            AddItem::Program {
+                name,
+                template,
+                path,
+                answers,
+            } => {
+                let options = AddProgramOptions {
+                    answers: answers.into_answers()?,
+                    template_path: template,
+                    path,
+                };
+                add_program(name, options).await?;
+            }
+            AddItem::Instruction {
+                name,
+                program,
+                accounts,
+                args,
+                path,
+                no_input,
+            } => {
+                let options = AddInstructionOptions {
+                    program,
+                    path,
+                    accounts,
+                    args,
+                    interactive: !no_input && std::io::stdin().is_terminal(),
+                };
+                add_instruction(&name, options)?;
+            }
+            AddItem::State {
+                name,
+                program,
+                fields,
+                seeds,
+                path,
+                no_input,
+            } => {
+                let options = AddStateOptions {
+                    program,
+                    path,
+                    fields,
+                    seeds,
+                    interactive: !no_input && std::io::stdin().is_terminal(),
+                };
+                add_state(&name, options)?;
+            }
+        },
+        Some(Commands::Config { action }) => match action {
+            ConfigAction::Get { key } => config_get(&key)?,
+            ConfigAction::Set { key, value } => config_set(&key, &value)?,
+            ConfigAction::List => config_list()?,
+        },
         None => {
             print_welcome_message();
         }
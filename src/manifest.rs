@@ -0,0 +1,343 @@
+use anyhow::Result;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use regex::Regex;
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::template::TemplateSource;
+
+/// Name of the manifest file at the root of a template
+pub const MANIFEST_FILE: &str = "gepetto.toml";
+
+/// Name of the ignore file at the root of a template, in gitignore syntax
+pub const IGNORE_FILE: &str = ".gepettoignore";
+
+/// Directory of Tera partials at the root of a template, never copied
+pub const PARTIALS_DIR: &str = "_partials";
+
+/// Ignore rules for templates without a `.gepettoignore`
+const DEFAULT_IGNORE: &[&str] = &["*.lock", "target"];
+
+/// Files rendered through Tera when the manifest has no `templated` list
+const DEFAULT_TEMPLATED: &[&str] = &[
+    "*.rs", "*.toml", "*.md", "*.json", "*.txt", "LICENSE", "README",
+];
+
+/// Context keys provided by gepetto itself, which manifests cannot redeclare
+pub const BUILTIN_VARIABLES: &[&str] = &[
+    "program_name_dash",
+    "program_name_underscore",
+    "program_name_readable",
+    "year",
+    "company_name",
+    "license",
+    "author_email",
+    "program_pubkey",
+    "program_keypair_path",
+    "deployer_pubkey",
+    "deployer_keypair_path",
+];
+
+/// Template manifest declaring the variables a template needs and how its
+/// files are processed, together with the template's ignore rules
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateManifest {
+    #[serde(default)]
+    pub variables: Vec<TemplateVariable>,
+    /// Glob patterns of files rendered through Tera
+    pub templated: Option<Vec<String>>,
+    /// Glob patterns of files copied as-is, even if they match `templated`
+    #[serde(default, alias = "raw")]
+    pub verbatim: Vec<String>,
+    #[serde(default)]
+    pub hooks: TemplateHooks,
+    #[serde(skip)]
+    templated_set: GlobSet,
+    #[serde(skip)]
+    verbatim_set: GlobSet,
+    #[serde(skip)]
+    ignore: Option<Gitignore>,
+}
+
+/// Steps run in the project once it has been generated
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct TemplateHooks {
+    /// Commands run in the project directory, rendered with Tera first
+    pub post_generate: Vec<String>,
+    /// Whether to run `git init` and create an initial commit
+    pub git_init: bool,
+}
+
+impl Default for TemplateHooks {
+    fn default() -> Self {
+        TemplateHooks {
+            post_generate: Vec::new(),
+            git_init: true,
+        }
+    }
+}
+
+/// Kind of value a template variable holds
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableKind {
+    #[default]
+    String,
+    Integer,
+    Bool,
+}
+
+/// A single variable declared in the template manifest
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateVariable {
+    /// Name of the variable in the Tera context
+    pub name: String,
+    /// Prompt text shown to the user
+    pub prompt: String,
+    #[serde(default, rename = "type")]
+    pub kind: VariableKind,
+    /// Default value, as it would be typed at the prompt
+    pub default: Option<String>,
+    /// Regular expression the answer must match
+    pub validation: Option<String>,
+    /// Allowed values, offered as a selection list
+    #[serde(default)]
+    pub choices: Vec<String>,
+}
+
+impl TemplateManifest {
+    /// Loads the manifest and ignore rules from the template root
+    ///
+    /// Templates without a manifest or `.gepettoignore` get the default rules:
+    /// lock files and `target` are skipped, and common text files are templated.
+    ///
+    /// # Arguments
+    /// * `source` - Template source to read from
+    ///
+    /// # Returns
+    /// The parsed and validated manifest
+    pub fn load(source: &TemplateSource) -> Result<Self> {
+        let root_entries = source.read_dir(Path::new(""))?;
+        let has_file = |name: &str| {
+            root_entries
+                .iter()
+                .any(|entry| !entry.is_dir && entry.path == Path::new(name))
+        };
+
+        let mut manifest = if has_file(MANIFEST_FILE) {
+            let content = read_utf8(source, MANIFEST_FILE)?;
+            let manifest: TemplateManifest = toml::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("Invalid {}: {}", MANIFEST_FILE, e))?;
+            manifest.validate()?;
+            manifest
+        } else {
+            Self::default()
+        };
+
+        manifest.templated_set = match &manifest.templated {
+            Some(patterns) => build_glob_set(patterns)?,
+            None => build_glob_set(DEFAULT_TEMPLATED)?,
+        };
+        manifest.verbatim_set = build_glob_set(&manifest.verbatim)?;
+
+        let ignore_content = if has_file(IGNORE_FILE) {
+            read_utf8(source, IGNORE_FILE)?
+        } else {
+            DEFAULT_IGNORE.join("\n")
+        };
+        let mut builder = GitignoreBuilder::new(".");
+        for line in ignore_content.lines() {
+            builder
+                .add_line(None, line)
+                .map_err(|e| anyhow::anyhow!("Invalid {}: {}", IGNORE_FILE, e))?;
+        }
+        manifest.ignore = Some(
+            builder
+                .build()
+                .map_err(|e| anyhow::anyhow!("Invalid {}: {}", IGNORE_FILE, e))?,
+        );
+
+        Ok(manifest)
+    }
+
+    /// Returns true if the entry is left out of the generated project
+    ///
+    /// # Arguments
+    /// * `path` - Entry path relative to the template root
+    /// * `is_dir` - Whether the entry is a directory
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if path == Path::new(MANIFEST_FILE)
+            || path == Path::new(IGNORE_FILE)
+            || path == Path::new(PARTIALS_DIR)
+        {
+            return true;
+        }
+        self.ignore
+            .as_ref()
+            .is_some_and(|ignore| ignore.matched(path, is_dir).is_ignore())
+    }
+
+    /// Returns true if the file is rendered through Tera
+    ///
+    /// # Arguments
+    /// * `path` - File path relative to the template root
+    pub fn is_templated(&self, path: &Path) -> bool {
+        self.templated_set.is_match(path) && !self.verbatim_set.is_match(path)
+    }
+
+    /// Checks variable names, validation patterns, choices and defaults
+    fn validate(&self) -> Result<()> {
+        let mut seen = Vec::new();
+        for variable in &self.variables {
+            let name = &variable.name;
+            if !is_identifier(name) {
+                return Err(anyhow::anyhow!(
+                    "Invalid variable name '{}' in {}",
+                    name,
+                    MANIFEST_FILE
+                ));
+            }
+            if BUILTIN_VARIABLES.contains(&name.as_str()) {
+                return Err(anyhow::anyhow!(
+                    "Variable '{}' in {} is provided by gepetto and cannot be redeclared",
+                    name,
+                    MANIFEST_FILE
+                ));
+            }
+            if seen.contains(&name) {
+                return Err(anyhow::anyhow!(
+                    "Variable '{}' is declared twice in {}",
+                    name,
+                    MANIFEST_FILE
+                ));
+            }
+            seen.push(name);
+
+            if variable.kind == VariableKind::Bool
+                && (variable.validation.is_some() || !variable.choices.is_empty())
+            {
+                return Err(anyhow::anyhow!(
+                    "Bool variable '{}' cannot have validation or choices",
+                    name
+                ));
+            }
+            variable.validation_regex()?;
+            for choice in &variable.choices {
+                variable.check(choice)?;
+            }
+            if let Some(default) = &variable.default {
+                variable.check(default)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TemplateVariable {
+    /// Compiles the validation pattern of the variable, if any
+    pub fn validation_regex(&self) -> Result<Option<Regex>> {
+        self.validation
+            .as_deref()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|e| {
+                    anyhow::anyhow!("Invalid validation pattern for '{}': {}", self.name, e)
+                })
+            })
+            .transpose()
+    }
+
+    /// Checks a raw answer against the variable's kind, pattern and choices
+    ///
+    /// # Arguments
+    /// * `value` - The answer as typed by the user
+    ///
+    /// # Returns
+    /// Ok(()) if the answer is acceptable, Err describing why it is not
+    pub fn check(&self, value: &str) -> Result<()> {
+        match self.kind {
+            VariableKind::String => {}
+            VariableKind::Integer => {
+                value.parse::<i64>().map_err(|_| {
+                    anyhow::anyhow!("'{}' is not a valid integer for '{}'", value, self.name)
+                })?;
+            }
+            VariableKind::Bool => {
+                value.parse::<bool>().map_err(|_| {
+                    anyhow::anyhow!("'{}' is not true or false for '{}'", value, self.name)
+                })?;
+            }
+        }
+        if let Some(regex) = self.validation_regex()? {
+            if !regex.is_match(value) {
+                return Err(anyhow::anyhow!(
+                    "'{}' does not match the pattern '{}' for '{}'",
+                    value,
+                    regex.as_str(),
+                    self.name
+                ));
+            }
+        }
+        if !self.choices.is_empty() && !self.choices.iter().any(|c| c == value) {
+            return Err(anyhow::anyhow!(
+                "'{}' is not one of the choices for '{}'",
+                value,
+                self.name
+            ));
+        }
+        Ok(())
+    }
+
+    /// Converts a checked answer into the value inserted in the Tera context
+    ///
+    /// # Arguments
+    /// * `value` - The answer as typed by the user
+    ///
+    /// # Returns
+    /// A JSON value of the declared kind
+    pub fn to_value(&self, value: &str) -> Result<serde_json::Value> {
+        self.check(value)?;
+        Ok(match self.kind {
+            VariableKind::String => serde_json::Value::from(value),
+            VariableKind::Integer => serde_json::Value::from(value.parse::<i64>()?),
+            VariableKind::Bool => serde_json::Value::from(value.parse::<bool>()?),
+        })
+    }
+}
+
+/// Reads a text file from the template root
+fn read_utf8(source: &TemplateSource, name: &str) -> Result<String> {
+    String::from_utf8(source.read_file(Path::new(name))?)
+        .map_err(|_| anyhow::anyhow!("{} is not valid UTF-8", name))
+}
+
+/// Compiles a list of glob patterns from the manifest
+///
+/// Like in gitignore files, a pattern without a `/` matches the file name in
+/// any directory.
+fn build_glob_set<S: AsRef<str>>(patterns: &[S]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = pattern.as_ref();
+        let full_pattern = if pattern.contains('/') {
+            pattern.trim_start_matches('/').to_string()
+        } else {
+            format!("**/{}", pattern)
+        };
+        let glob = Glob::new(&full_pattern).map_err(|e| {
+            anyhow::anyhow!("Invalid pattern '{}' in {}: {}", pattern, MANIFEST_FILE, e)
+        })?;
+        builder.add(glob);
+    }
+    Ok(builder.build()?)
+}
+
+/// Returns true if `name` is usable as a Tera variable name
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
@@ -0,0 +1,109 @@
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Directory a project is generated in before being moved to its final place
+///
+/// The staging directory lives next to the target so the final rename stays on
+/// the same filesystem. It is removed on drop unless `commit` succeeded, which
+/// cleans up partial output when generation fails halfway.
+#[derive(Debug)]
+pub struct StagingDir {
+    path: PathBuf,
+    target: PathBuf,
+    committed: bool,
+    /// Directory the Ctrl-C listener removes, cleared once it is committed or dropped
+    pending: Arc<Mutex<Option<PathBuf>>>,
+}
+
+impl StagingDir {
+    /// Creates an empty staging directory for the given target
+    ///
+    /// # Arguments
+    /// * `target` - Final project directory, which must not exist yet
+    ///
+    /// # Returns
+    /// The staging directory guard
+    pub fn create(target: &Path) -> Result<Self> {
+        let file_name = target
+            .file_name()
+            .ok_or_else(|| anyhow::anyhow!("Invalid project directory '{}'", target.display()))?;
+        let path = target.with_file_name(format!(
+            ".{}.gepetto-{}",
+            file_name.to_string_lossy(),
+            std::process::id()
+        ));
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+        fs::create_dir_all(&path)?;
+
+        Ok(StagingDir {
+            pending: Arc::new(Mutex::new(Some(path.clone()))),
+            path,
+            target: target.to_path_buf(),
+            committed: false,
+        })
+    }
+
+    /// Path of the staging directory files should be written to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Removes the staging directory if the process is interrupted with Ctrl-C
+    ///
+    /// Listening for Ctrl-C replaces the default SIGINT disposition for the
+    /// rest of the process, so the listener stays for the whole command and
+    /// exits with status 130 on Ctrl-C, cleaning up first while the staging
+    /// directory is still pending.
+    pub fn remove_on_interrupt(&self) {
+        let pending = Arc::clone(&self.pending);
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                let path = pending.lock().ok().and_then(|mut path| path.take());
+                if let Some(path) = path {
+                    let _ = fs::remove_dir_all(&path);
+                    eprintln!("\nInterrupted, partial project removed");
+                } else {
+                    eprintln!("\nInterrupted");
+                }
+                std::process::exit(130);
+            }
+        });
+    }
+
+    /// Stops the Ctrl-C listener from removing the staging directory
+    fn settle(&self) {
+        if let Ok(mut path) = self.pending.lock() {
+            *path = None;
+        }
+    }
+
+    /// Moves the staging directory to the target
+    ///
+    /// # Returns
+    /// Result indicating success or failure
+    pub fn commit(mut self) -> Result<()> {
+        fs::rename(&self.path, &self.target).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to move generated project to '{}': {}",
+                self.target.display(),
+                e
+            )
+        })?;
+        self.settle();
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        self.settle();
+        if !self.committed {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
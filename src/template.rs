@@ -1,12 +1,177 @@
 use anyhow::Result;
-use serde_json::json;
-use solana_sdk::signature::Keypair;
+use include_dir::{include_dir, Dir, DirEntry};
+use regex::Regex;
+use solana_sdk::signature::Signer;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tera::{Context, Tera};
 
 use crate::config::ProjectConfig;
-use crate::validation::{should_skip_file, should_template_file};
+use crate::filters::register_filters;
+use crate::keypair::{read_keypair_file, write_keypair_file};
+use crate::license::add_spdx_headers;
+use crate::manifest::{TemplateManifest, PARTIALS_DIR};
+
+/// Built-in project template, embedded into the binary at compile time
+pub static TEMPLATE_DIR: Dir<'static> = include_dir!("$OUT_DIR/template");
+
+/// Unix modes of the built-in template files, recorded by the build script
+static TEMPLATE_MODES: &[(&str, u32)] = include!(concat!(env!("OUT_DIR"), "/template_modes.rs"));
+
+#[cfg(unix)]
+fn local_file_mode(path: &Path) -> Result<Option<u32>> {
+    use std::os::unix::fs::PermissionsExt;
+    Ok(Some(fs::metadata(path)?.permissions().mode() & 0o777))
+}
+
+#[cfg(not(unix))]
+fn local_file_mode(_path: &Path) -> Result<Option<u32>> {
+    Ok(None)
+}
+
+/// Where template files are read from
+#[derive(Debug)]
+pub enum TemplateSource {
+    /// The built-in template compiled into the binary
+    Embedded(&'static Dir<'static>),
+    /// A custom template directory on the local filesystem
+    Local(PathBuf),
+}
+
+/// A single entry of a template directory
+#[derive(Debug)]
+pub struct TemplateEntry {
+    /// Path relative to the template root
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+/// What happens to a template file during scaffolding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileAction {
+    /// Rendered through Tera
+    Templated,
+    /// Copied without rendering
+    Verbatim,
+    /// Left out of the generated project
+    Skipped,
+}
+
+/// A template file resolved to its place in the generated project
+#[derive(Debug)]
+pub struct RenderedFile {
+    /// Path relative to the template root
+    pub template_path: PathBuf,
+    /// Path relative to the project root
+    pub output_path: PathBuf,
+    pub action: FileAction,
+    /// Final contents, empty for skipped files
+    pub contents: Vec<u8>,
+    /// Unix permission bits of the template file, if known
+    pub mode: Option<u32>,
+}
+
+impl TemplateSource {
+    /// Resolves the template to use, falling back to the built-in one
+    ///
+    /// # Arguments
+    /// * `template_path` - Optional path to a custom template directory
+    ///
+    /// # Returns
+    /// A local source for the given path, or the embedded template if None
+    pub fn resolve(template_path: Option<PathBuf>) -> Self {
+        match template_path {
+            Some(path) => {
+                let expanded = shellexpand::tilde(&path.to_string_lossy()).into_owned();
+                TemplateSource::Local(PathBuf::from(expanded))
+            }
+            None => TemplateSource::Embedded(&TEMPLATE_DIR),
+        }
+    }
+
+    /// Human readable description of the source, used in messages
+    pub fn describe(&self) -> String {
+        match self {
+            TemplateSource::Embedded(_) => "built-in template".to_string(),
+            TemplateSource::Local(root) => root.display().to_string(),
+        }
+    }
+
+    /// Lists the entries of a template directory, sorted by path
+    ///
+    /// # Arguments
+    /// * `dir` - Directory path relative to the template root
+    ///
+    /// # Returns
+    /// The entries directly contained in `dir`
+    pub fn read_dir(&self, dir: &Path) -> Result<Vec<TemplateEntry>> {
+        let mut entries = match self {
+            TemplateSource::Embedded(root) => {
+                let dir = if dir.as_os_str().is_empty() {
+                    *root
+                } else {
+                    root.get_dir(dir).ok_or_else(|| {
+                        anyhow::anyhow!("Template directory '{}' not found", dir.display())
+                    })?
+                };
+                dir.entries()
+                    .iter()
+                    .map(|entry| TemplateEntry {
+                        path: entry.path().to_path_buf(),
+                        is_dir: matches!(entry, DirEntry::Dir(_)),
+                    })
+                    .collect::<Vec<_>>()
+            }
+            TemplateSource::Local(root) => {
+                let mut entries = Vec::new();
+                for entry in fs::read_dir(root.join(dir))? {
+                    let entry = entry?;
+                    entries.push(TemplateEntry {
+                        path: dir.join(entry.file_name()),
+                        is_dir: entry.file_type()?.is_dir(),
+                    });
+                }
+                entries
+            }
+        };
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(entries)
+    }
+
+    /// Reads the raw contents of a template file
+    ///
+    /// # Arguments
+    /// * `file` - File path relative to the template root
+    ///
+    /// # Returns
+    /// The file contents as bytes
+    pub fn read_file(&self, file: &Path) -> Result<Vec<u8>> {
+        match self {
+            TemplateSource::Embedded(root) => root
+                .get_file(file)
+                .map(|f| f.contents().to_vec())
+                .ok_or_else(|| anyhow::anyhow!("Template file '{}' not found", file.display())),
+            TemplateSource::Local(root) => Ok(fs::read(root.join(file))?),
+        }
+    }
+
+    /// Returns the Unix permission bits of a template file
+    ///
+    /// # Arguments
+    /// * `file` - File path relative to the template root
+    ///
+    /// # Returns
+    /// The mode of the file, or None where permissions are not available
+    pub fn file_mode(&self, file: &Path) -> Result<Option<u32>> {
+        match self {
+            TemplateSource::Embedded(_) => Ok(TEMPLATE_MODES
+                .iter()
+                .find(|(path, _)| Path::new(path) == file)
+                .map(|(_, mode)| *mode)),
+            TemplateSource::Local(root) => local_file_mode(&root.join(file)),
+        }
+    }
+}
 
 /// Creates a Tera template context from project configuration
 ///
@@ -22,127 +187,610 @@ pub fn create_template_context(config: &ProjectConfig) -> Context {
     context.insert("program_name_readable", &config.program_name_readable);
     context.insert("year", &config.year);
     context.insert("company_name", &config.company_name);
+    context.insert("license", config.license.spdx());
+    context.insert("author_email", &config.author_email);
     context.insert("program_pubkey", &config.program_pubkey);
+    context.insert(
+        "program_keypair_path",
+        &command_path(&config.program_keypair_path),
+    );
+    context.insert(
+        "deployer_pubkey",
+        config.deployer_pubkey.as_deref().unwrap_or_default(),
+    );
+    context.insert(
+        "deployer_keypair_path",
+        &command_path(&config.deployer_keypair_path),
+    );
+    for (name, value) in &config.variables {
+        context.insert(name, value);
+    }
     context
 }
 
-/// Copies a single file with optional template processing
+/// Formats an optional path for use in shell commands, empty if None
+///
+/// Relative paths get a leading `./` so they read as paths in commands.
+fn command_path(path: &Option<PathBuf>) -> String {
+    path.as_ref()
+        .map(|path| Path::new(".").join(path).display().to_string())
+        .unwrap_or_default()
+}
+
+/// Renders a single file with optional template processing
+///
+/// Files that are not templated, or are not valid UTF-8, are copied
+/// byte-for-byte.
 ///
 /// # Arguments
-/// * `src` - Source file path
-/// * `dst` - Destination file path
+/// * `source` - Template source to read from
+/// * `manifest` - Template manifest deciding which files are rendered
+/// * `src` - Source file path relative to the template root
 /// * `tera` - Tera template engine instance
 /// * `context` - Template context for rendering
 ///
 /// # Returns
-/// Result indicating success or failure
-pub fn copy_file_with_templating(
+/// How the file was processed and its final contents, or an error naming the
+/// file and position on render errors
+pub fn render_file(
+    source: &TemplateSource,
+    manifest: &TemplateManifest,
     src: &Path,
-    dst: &Path,
     tera: &mut Tera,
     context: &Context,
-) -> Result<()> {
-    let content = fs::read_to_string(src)?;
+) -> Result<(FileAction, Vec<u8>)> {
+    let bytes = source.read_file(src)?;
+    if !manifest.is_templated(src) {
+        return Ok((FileAction::Verbatim, bytes));
+    }
 
-    let final_content = if should_template_file(src) {
-        // Try to render as template, fallback to original content if it fails
-        match tera.render_str(&content, context) {
-            Ok(rendered) => rendered,
-            Err(_) => content, // If templating fails, use original content
+    match String::from_utf8(bytes) {
+        Ok(content) => {
+            let rendered = tera
+                .render_str(&content, context)
+                .map_err(|e| render_error(src, &content, e))?;
+            Ok((FileAction::Templated, rendered.into_bytes()))
         }
-    } else {
-        content
-    };
+        // Binary content cannot hold Tera expressions
+        Err(e) => Ok((FileAction::Verbatim, e.into_bytes())),
+    }
+}
 
-    fs::write(dst, final_content)?;
-    Ok(())
+/// Builds a readable error for a template that failed to render
+///
+/// Tera reports one-off templates as `__tera_one_off`, so the name is replaced
+/// with the template file path. Parse errors carry their own line and column;
+/// for missing variables the first line using the variable is reported.
+///
+/// # Arguments
+/// * `path` - Template file path relative to the template root
+/// * `content` - Raw template content
+/// * `error` - The error returned by Tera
+///
+/// # Returns
+/// An error naming the file and, when known, the line and column
+fn render_error(path: &Path, content: &str, error: tera::Error) -> anyhow::Error {
+    let mut messages = Vec::new();
+    let mut current: Option<&dyn std::error::Error> = Some(&error);
+    while let Some(err) = current {
+        messages.push(
+            err.to_string()
+                .replace("'__tera_one_off'", &format!("'{}'", path.display())),
+        );
+        current = err.source();
+    }
+    // The outermost message only repeats the template name
+    if messages.len() > 1 {
+        messages.remove(0);
+    }
+    let details = messages.join("\n");
+
+    let position = Regex::new(r"-->\s*(\d+):(\d+)")
+        .ok()
+        .and_then(|re| re.captures(&details))
+        .map(|caps| (caps[1].to_string(), caps[2].to_string()))
+        .or_else(|| {
+            let name = Regex::new(r"Variable `([^`]+)` not found")
+                .ok()?
+                .captures(&details)?[1]
+                .to_string();
+            content.lines().enumerate().find_map(|(line, text)| {
+                let column = text.find(&name)?;
+                (text.contains("{{") || text.contains("{%"))
+                    .then(|| ((line + 1).to_string(), (column + 1).to_string()))
+            })
+        });
+
+    match position {
+        Some((line, column)) => anyhow::anyhow!(
+            "Failed to render template '{}' at line {}, column {}:\n{}",
+            path.display(),
+            line,
+            column,
+            details
+        ),
+        None => anyhow::anyhow!(
+            "Failed to render template '{}':\n{}",
+            path.display(),
+            details
+        ),
+    }
 }
 
-/// Recursively copies a directory with template processing
+/// Renders a single file or directory name from the template
+///
+/// Names may contain Tera expressions, e.g. `{{program_name_dash}}`. A name
+/// that renders to an empty string means the entry is left out.
 ///
 /// # Arguments
-/// * `src` - Source directory path
-/// * `dst` - Destination directory path
+/// * `path` - Entry path relative to the template root
 /// * `tera` - Tera template engine instance
 /// * `context` - Template context for rendering
-/// * `program_name_dash` - Program name for directory renaming
+///
+/// # Returns
+/// The rendered name, or None if the entry should be skipped
+pub fn render_file_name(path: &Path, tera: &mut Tera, context: &Context) -> Result<Option<String>> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if !name.contains("{{") && !name.contains("{%") {
+        return Ok(Some(name));
+    }
+
+    let rendered = tera
+        .render_str(&name, context)
+        .map_err(|e| render_error(path, &name, e))?;
+    let rendered = rendered.trim();
+    if rendered.is_empty() {
+        return Ok(None);
+    }
+    if rendered.contains(['/', '\\']) || rendered == "." || rendered == ".." {
+        return Err(anyhow::anyhow!(
+            "Template path '{}' renders to invalid name '{}'",
+            path.display(),
+            rendered
+        ));
+    }
+    Ok(Some(rendered.to_string()))
+}
+
+/// Recursively renders a template directory
+///
+/// # Arguments
+/// * `source` - Template source to read from
+/// * `manifest` - Template manifest
+/// * `src` - Source directory path relative to the template root
+/// * `dst` - Destination directory path relative to the project root
+/// * `tera` - Tera template engine instance
+/// * `context` - Template context for rendering
+/// * `files` - Accumulates every file, including skipped ones
 ///
 /// # Returns
 /// Result indicating success or failure
-pub fn copy_dir_recursive(
+pub fn render_dir_recursive(
+    source: &TemplateSource,
+    manifest: &TemplateManifest,
     src: &Path,
     dst: &Path,
     tera: &mut Tera,
     context: &Context,
-    program_name_dash: &str,
+    files: &mut Vec<RenderedFile>,
 ) -> Result<()> {
-    if !dst.exists() {
-        fs::create_dir_all(dst)?;
-    }
-
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let path = entry.path();
-        let file_name = entry.file_name();
-        let file_name_str = file_name.to_string_lossy();
+    for entry in source.read_dir(src)? {
+        let Some(file_name) = entry.path.file_name() else {
+            continue;
+        };
 
-        // Skip certain files
-        if should_skip_file(&file_name_str) {
+        // Skip ignored files, and the files which only describe the template
+        let dest_name = if manifest.is_ignored(&entry.path, entry.is_dir) {
+            None
+        } else {
+            render_file_name(&entry.path, tera, context)?
+        };
+        let Some(dest_name) = dest_name else {
+            files.push(RenderedFile {
+                template_path: entry.path.clone(),
+                output_path: dst.join(file_name),
+                action: FileAction::Skipped,
+                contents: Vec::new(),
+                mode: None,
+            });
             continue;
+        };
+        let dest_path = dst.join(dest_name);
+
+        if entry.is_dir {
+            render_dir_recursive(
+                source,
+                manifest,
+                &entry.path,
+                &dest_path,
+                tera,
+                context,
+                files,
+            )?;
+        } else {
+            let (action, contents) = render_file(source, manifest, &entry.path, tera, context)?;
+            let mode = source.file_mode(&entry.path)?;
+            files.push(RenderedFile {
+                template_path: entry.path,
+                output_path: dest_path,
+                action,
+                contents,
+                mode,
+            });
         }
+    }
 
-        let mut dest_path = dst.join(&file_name);
+    Ok(())
+}
 
-        // Rename counter-pinocchio directory to the actual program name
-        if file_name_str == "counter-pinocchio" {
-            dest_path = dst.join(program_name_dash);
-        }
+/// Loads the template's partials into the Tera instance
+///
+/// Every file below `_partials/` is registered under its path relative to
+/// that directory, so templates can `{% include "license_header.rs" %}`,
+/// `{% extends %}` or `{% import %}` macros from it.
+///
+/// # Arguments
+/// * `source` - Template source to read from
+/// * `tera` - Tera template engine instance
+///
+/// # Returns
+/// Result indicating success or failure
+pub fn load_partials(source: &TemplateSource, tera: &mut Tera) -> Result<()> {
+    let root = Path::new(PARTIALS_DIR);
+    let has_partials = source
+        .read_dir(Path::new(""))?
+        .iter()
+        .any(|entry| entry.is_dir && entry.path == root);
+    if !has_partials {
+        return Ok(());
+    }
 
-        if path.is_dir() {
-            copy_dir_recursive(&path, &dest_path, tera, context, program_name_dash)?;
-        } else {
-            copy_file_with_templating(&path, &dest_path, tera, context)?;
+    let mut partials = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in source.read_dir(&dir)? {
+            if entry.is_dir {
+                dirs.push(entry.path);
+                continue;
+            }
+            let content = String::from_utf8(source.read_file(&entry.path)?).map_err(|_| {
+                anyhow::anyhow!("Partial '{}' is not valid UTF-8", entry.path.display())
+            })?;
+            let name = entry
+                .path
+                .strip_prefix(root)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            partials.push((name, content));
         }
     }
 
+    tera.add_raw_templates(partials)
+        .map_err(|e| anyhow::anyhow!("Failed to load partials: {}", error_chain(&e)))?;
+    Ok(())
+}
+
+/// Joins an error and all its sources into one message
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut messages = vec![error.to_string()];
+    let mut current = error.source();
+    while let Some(err) = current {
+        messages.push(err.to_string());
+        current = err.source();
+    }
+    messages.join("\n")
+}
+
+/// Renders every template file in memory, without touching the disk
+///
+/// # Arguments
+/// * `source` - Template source to read from
+/// * `manifest` - Template manifest
+/// * `tera` - Tera template engine instance
+/// * `context` - Template context for rendering
+///
+/// # Returns
+/// The rendered files with paths relative to the project root
+pub fn render_template_files(
+    source: &TemplateSource,
+    manifest: &TemplateManifest,
+    tera: &mut Tera,
+    context: &Context,
+) -> Result<Vec<RenderedFile>> {
+    load_partials(source, tera)?;
+
+    let mut files = Vec::new();
+    render_dir_recursive(
+        source,
+        manifest,
+        Path::new(""),
+        Path::new(""),
+        tera,
+        context,
+        &mut files,
+    )?;
+    Ok(files)
+}
+
+/// Renders a whole project from the template and project configuration
+///
+/// With `spdx_headers`, every Rust file gets a license header.
+///
+/// # Arguments
+/// * `source` - Template source to read from
+/// * `manifest` - Template manifest
+/// * `config` - The project configuration
+///
+/// # Returns
+/// The rendered files with paths relative to the project root
+pub fn render_project_files(
+    source: &TemplateSource,
+    manifest: &TemplateManifest,
+    config: &ProjectConfig,
+) -> Result<Vec<RenderedFile>> {
+    let mut tera = Tera::default();
+    register_filters(&mut tera);
+    let context = create_template_context(config);
+    let mut files = render_template_files(source, manifest, &mut tera, &context)?;
+    if config.spdx_headers {
+        add_spdx_headers(&mut files, config.license);
+    }
+    Ok(files)
+}
+
+/// Renders a single partial of the template on its own
+///
+/// # Arguments
+/// * `source` - Template source to read from
+/// * `config` - The project configuration
+/// * `name` - Partial path relative to `_partials/`, e.g. `deploy.md`
+///
+/// # Returns
+/// The rendered partial, or None if the template has no such partial
+pub fn render_partial(
+    source: &TemplateSource,
+    config: &ProjectConfig,
+    name: &str,
+) -> Result<Option<String>> {
+    let mut tera = Tera::default();
+    register_filters(&mut tera);
+    load_partials(source, &mut tera)?;
+    if !tera.get_template_names().any(|n| n == name) {
+        return Ok(None);
+    }
+    let rendered = tera
+        .render(name, &create_template_context(config))
+        .map_err(|e| anyhow::anyhow!("Failed to render partial '{}': {}", name, error_chain(&e)))?;
+    Ok(Some(rendered))
+}
+
+/// Writes a single rendered file, creating parent directories as needed
+///
+/// # Arguments
+/// * `dest_path` - Where to write the file
+/// * `file` - The rendered file, whose Unix mode is preserved when known
+///
+/// # Returns
+/// Result indicating success or failure
+pub fn write_rendered_file(dest_path: &Path, file: &RenderedFile) -> Result<()> {
+    if let Some(parent) = dest_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| anyhow::anyhow!("Failed to create '{}': {}", parent.display(), e))?;
+    }
+    fs::write(dest_path, &file.contents)
+        .map_err(|e| anyhow::anyhow!("Failed to write '{}': {}", dest_path.display(), e))?;
+    if let Some(mode) = file.mode {
+        set_file_mode(dest_path, mode)?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_file_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_file_mode(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+/// Writes rendered files below the project directory
+///
+/// # Arguments
+/// * `files` - Files produced by `render_template_files`
+/// * `project_dir` - Path to the project directory
+///
+/// # Returns
+/// Result indicating success or failure
+pub fn write_rendered_files(files: &[RenderedFile], project_dir: &Path) -> Result<()> {
+    for file in files.iter().filter(|f| f.action != FileAction::Skipped) {
+        write_rendered_file(&project_dir.join(&file.output_path), file)?;
+    }
     Ok(())
 }
 
 /// Copies all template files to the project directory
 ///
 /// # Arguments
-/// * `template_dir` - Path to the template directory
+/// * `source` - Template source to read from
+/// * `manifest` - Template manifest
 /// * `project_dir` - Path to the project directory
 /// * `tera` - Tera template engine instance
 /// * `context` - Template context for rendering
-/// * `program_name_dash` - Program name for directory renaming
 ///
 /// # Returns
 /// Result indicating success or failure
 pub fn copy_template_files(
-    template_dir: &Path,
+    source: &TemplateSource,
+    manifest: &TemplateManifest,
     project_dir: &Path,
     tera: &mut Tera,
     context: &Context,
-    program_name_dash: &str,
 ) -> Result<()> {
-    copy_dir_recursive(template_dir, project_dir, tera, context, program_name_dash)?;
-    Ok(())
+    let files = render_template_files(source, manifest, tera, context)?;
+    write_rendered_files(&files, project_dir)
 }
 
-/// Creates the program-id.json file in the project directory
+/// Writes the keypairs kept in the project, such as `program-id.json`
+///
+/// The files hold secret keys and are only readable by their owner. A file
+/// that already holds the same keypair is left alone, a different one is an
+/// error rather than being replaced.
 ///
 /// # Arguments
 /// * `project_dir` - Path to the project directory
-/// * `program_keypair` - The program keypair to serialize
+/// * `config` - Project configuration with the keypairs and their paths
 ///
 /// # Returns
 /// Result indicating success or failure
-pub fn create_program_id_file(project_dir: &Path, program_keypair: &Keypair) -> Result<()> {
-    let program_id_json = json!(program_keypair.to_bytes().as_ref());
-    fs::write(
-        project_dir.join("program-id.json"),
-        serde_json::to_string(&program_id_json)?,
-    )?;
+pub fn create_keypair_files(project_dir: &Path, config: &ProjectConfig) -> Result<()> {
+    for (path, keypair) in config.keypair_files() {
+        if path.is_absolute() {
+            continue;
+        }
+        let dest_path = project_dir.join(path);
+        if dest_path.exists() {
+            let existing = read_keypair_file(&dest_path)?.pubkey();
+            if existing != keypair.pubkey() {
+                return Err(anyhow::anyhow!(
+                    "'{}' already holds keypair {}, not {}",
+                    dest_path.display(),
+                    existing,
+                    keypair.pubkey()
+                ));
+            }
+            continue;
+        }
+        write_keypair_file(&dest_path, keypair)?;
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::license::License;
+    use std::collections::BTreeMap;
+
+    fn config() -> ProjectConfig {
+        ProjectConfig {
+            program_name_dash: "my-counter".to_string(),
+            program_name_underscore: "my_counter".to_string(),
+            program_name_readable: "My Counter".to_string(),
+            company_name: "Acme".to_string(),
+            author_email: String::new(),
+            year: 2025,
+            license: License::default(),
+            spdx_headers: false,
+            program_pubkey: "11111111111111111111111111111111".to_string(),
+            program_keypair: None,
+            program_keypair_path: None,
+            deployer_pubkey: None,
+            deployer_keypair: None,
+            deployer_keypair_path: None,
+            variables: BTreeMap::new(),
+        }
+    }
+
+    /// Renders a local template made of the given files
+    fn render(name: &str, files: &[(&str, &str)]) -> Result<Vec<RenderedFile>> {
+        let root =
+            std::env::temp_dir().join(format!("gepetto-template-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (path, content) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, content)?;
+        }
+        let source = TemplateSource::Local(root.clone());
+        let rendered = TemplateManifest::load(&source)
+            .and_then(|manifest| render_project_files(&source, &manifest, &config()));
+        fs::remove_dir_all(&root)?;
+        rendered
+    }
+
+    #[test]
+    fn test_unknown_variable_position() {
+        let error = render(
+            "unknown-variable",
+            &[
+                ("Cargo.toml", "[package]\nname = \"{{ program_name_dash }}\"\n"),
+                (
+                    "src/lib.rs",
+                    "//! {{ program_name_readable }}\n\npub const NAME: &str = \"{{ program_nam_dash }}\";\n",
+                ),
+            ],
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            error.starts_with("Failed to render template 'src/lib.rs' at line 3, column 28:"),
+            "{}",
+            error
+        );
+        assert!(error.contains("program_nam_dash"), "{}", error);
+    }
+
+    #[test]
+    fn test_syntax_error_position() {
+        let error = render(
+            "syntax-error",
+            &[
+                (
+                    "Cargo.toml",
+                    "[package]\nname = \"{{ program_name_dash }\"\n",
+                ),
+                ("src/lib.rs", "\n"),
+            ],
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            error.starts_with("Failed to render template 'Cargo.toml' at line 2,"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_verbatim_files_are_not_rendered() {
+        let script = "#!/bin/sh\necho \"{{ not_a_variable }}\"\n";
+        let files = render(
+            "verbatim",
+            &[
+                (
+                    "gepetto.toml",
+                    "templated = [\"*.toml\", \"*.sh\"]\nraw = [\"scripts/*.sh\"]\n",
+                ),
+                (
+                    "Cargo.toml",
+                    "[package]\nname = \"{{ program_name_dash }}\"\n",
+                ),
+                ("scripts/run.sh", script),
+                ("build.sh", "echo {{ program_name_dash }}\n"),
+            ],
+        )
+        .unwrap();
+        let file = |path: &str| {
+            files
+                .iter()
+                .find(|f| f.output_path == Path::new(path))
+                .unwrap()
+        };
+        assert_eq!(file("scripts/run.sh").contents, script.as_bytes());
+        assert_eq!(file("scripts/run.sh").action, FileAction::Verbatim);
+        assert_eq!(file("build.sh").contents, b"echo my-counter\n");
+        assert_eq!(
+            file("Cargo.toml").contents,
+            b"[package]\nname = \"my-counter\"\n"
+        );
+    }
+}
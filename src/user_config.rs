@@ -0,0 +1,157 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::license::License;
+
+/// Environment variable overriding the location of the user config file
+const CONFIG_PATH_ENV: &str = "GEPETTO_CONFIG";
+
+/// Keys of the user config, in the order `gepetto config list` prints them
+pub const CONFIG_KEYS: &[&str] = &[
+    "company",
+    "license",
+    "author_email",
+    "template",
+    "output_dir",
+    "keystore",
+];
+
+/// User-level defaults, stored in `~/.config/gepetto/config.toml`
+///
+/// Every value is optional. Flags, environment variables and answers files
+/// take precedence, and prompts are prefilled with these values.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserConfig {
+    /// Default company name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub company: Option<String>,
+    /// Default license, as an SPDX identifier
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// Author email, available to templates as `author_email`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author_email: Option<String>,
+    /// Template directory used when `--template` is not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Directory new projects are created in, instead of the current one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_dir: Option<String>,
+    /// Directory generated keypairs are stored in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keystore: Option<String>,
+}
+
+impl UserConfig {
+    /// Path of the user config file
+    ///
+    /// `GEPETTO_CONFIG` overrides the default location in the platform's
+    /// config directory.
+    ///
+    /// # Returns
+    /// The config file path, which may not exist yet
+    pub fn path() -> Result<PathBuf> {
+        if let Ok(path) = std::env::var(CONFIG_PATH_ENV) {
+            return Ok(PathBuf::from(shellexpand::tilde(&path).into_owned()));
+        }
+        dirs::config_dir()
+            .map(|dir| dir.join("gepetto").join("config.toml"))
+            .ok_or_else(|| anyhow::anyhow!("Cannot locate the user config directory"))
+    }
+
+    /// Loads the user config, or the empty config if there is no file
+    ///
+    /// # Returns
+    /// The parsed user config
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read '{}': {}", path.display(), e))?;
+        toml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid user config '{}': {}", path.display(), e))
+    }
+
+    /// Writes the user config, creating its directory if needed
+    ///
+    /// # Returns
+    /// The path the config was written to
+    pub fn save(&self) -> Result<PathBuf> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, toml::to_string(self)?)
+            .map_err(|e| anyhow::anyhow!("Failed to write '{}': {}", path.display(), e))?;
+        Ok(path)
+    }
+
+    /// Returns the value of a key
+    ///
+    /// # Arguments
+    /// * `key` - One of `CONFIG_KEYS`
+    ///
+    /// # Returns
+    /// The value, None if unset, or Err for an unknown key
+    pub fn get(&self, key: &str) -> Result<Option<&str>> {
+        Ok(self.field(key)?.as_deref())
+    }
+
+    /// Sets the value of a key, or unsets it when the value is empty
+    ///
+    /// # Arguments
+    /// * `key` - One of `CONFIG_KEYS`
+    /// * `value` - New value, licenses are stored as SPDX expressions
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let field = match key {
+            "company" => &mut self.company,
+            "license" => &mut self.license,
+            "author_email" => &mut self.author_email,
+            "template" => &mut self.template,
+            "output_dir" => &mut self.output_dir,
+            "keystore" => &mut self.keystore,
+            _ => return Err(unknown_key(key)),
+        };
+        *field = match value.trim() {
+            "" => None,
+            value if key == "license" => Some(value.parse::<License>()?.spdx().to_string()),
+            value => Some(value.to_string()),
+        };
+        Ok(())
+    }
+
+    /// Path-valued keys with `~` expanded
+    ///
+    /// # Arguments
+    /// * `key` - `template`, `output_dir` or `keystore`
+    pub fn path_value(&self, key: &str) -> Result<Option<PathBuf>> {
+        Ok(self
+            .get(key)?
+            .map(|value| PathBuf::from(shellexpand::tilde(value).into_owned())))
+    }
+
+    fn field(&self, key: &str) -> Result<&Option<String>> {
+        match key {
+            "company" => Ok(&self.company),
+            "license" => Ok(&self.license),
+            "author_email" => Ok(&self.author_email),
+            "template" => Ok(&self.template),
+            "output_dir" => Ok(&self.output_dir),
+            "keystore" => Ok(&self.keystore),
+            _ => Err(unknown_key(key)),
+        }
+    }
+}
+
+fn unknown_key(key: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "Unknown config key '{}', expected one of: {}",
+        key,
+        CONFIG_KEYS.join(", ")
+    )
+}
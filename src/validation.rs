@@ -1,66 +1,326 @@
 use anyhow::Result;
+use heck::ToKebabCase;
 use std::path::Path;
 
+use crate::template::TemplateSource;
+
+/// Rust keywords, strict and reserved, which cannot name a crate
+pub(crate) const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Crate names taken by the standard library, Cargo or the template's dependencies
+const RESERVED_NAMES: &[&str] = &[
+    "alloc",
+    "build",
+    "core",
+    "deps",
+    "examples",
+    "incremental",
+    "mollusk_svm",
+    "pinocchio",
+    "pinocchio_pubkey",
+    "pinocchio_system",
+    "proc_macro",
+    "solana_program",
+    "solana_sdk",
+    "std",
+    "test",
+];
+
+/// File names Windows reserves, which Cargo refuses as package names
+const WINDOWS_RESERVED: &[&str] = &[
+    "aux", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8", "com9", "con", "lpt1",
+    "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9", "nul", "prn",
+];
+
+/// Longest package name crates.io accepts
+const MAX_NAME_LENGTH: usize = 64;
+
+/// Validates a program name before anything is generated
+///
+/// The name becomes the Cargo package (`program_name_dash`), the crate path
+/// used in tests (`program_name_underscore`) and the SBF artifacts
+/// `target/deploy/<program_name_underscore>.so` and `-keypair.json`, so it
+/// must be valid for all three.
+///
+/// # Arguments
+/// * `name` - The program name as given by the user
+///
+/// # Returns
+/// Ok(()) if the name is usable, Err with the reason and a suggested name otherwise
+pub fn validate_program_name(name: &str) -> Result<()> {
+    match program_name_problem(name) {
+        None => Ok(()),
+        Some(problem) => Err(match suggest_program_name(name) {
+            Some(suggestion) => anyhow::anyhow!(
+                "Invalid program name '{}': {}, try '{}'",
+                name,
+                problem,
+                suggestion
+            ),
+            None => anyhow::anyhow!("Invalid program name '{}': {}", name, problem),
+        }),
+    }
+}
+
+/// Suggests a valid program name close to an invalid one
+///
+/// # Arguments
+/// * `name` - The rejected program name
+///
+/// # Returns
+/// A kebab-case name that passes validation, if one can be derived
+pub fn suggest_program_name(name: &str) -> Option<String> {
+    let kebab = name.to_kebab_case();
+    let mut suggestion = kebab
+        .trim_start_matches(|c: char| c.is_ascii_digit() || c == '-')
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .take(MAX_NAME_LENGTH)
+        .collect::<String>()
+        .trim_end_matches('-')
+        .to_string();
+    if program_name_problem(&suggestion).is_some() {
+        suggestion.push_str("-program");
+    }
+    match program_name_problem(&suggestion) {
+        None if suggestion != name => Some(suggestion),
+        _ => None,
+    }
+}
+
+/// Returns why a program name is invalid, or None if it is valid
+fn program_name_problem(name: &str) -> Option<String> {
+    let underscore = name.replace('-', "_");
+
+    if name.is_empty() {
+        return Some("the name is empty".to_string());
+    }
+    if name.len() > MAX_NAME_LENGTH {
+        return Some(format!(
+            "names are limited to {} characters",
+            MAX_NAME_LENGTH
+        ));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !(c.is_ascii_lowercase() || c.is_ascii_digit() || *c == '-' || *c == '_'))
+    {
+        return Some(match c.is_ascii_uppercase() {
+            true => "names must be lowercase".to_string(),
+            false => format!(
+                "'{}' is not allowed, use lowercase letters, digits and '-'",
+                c
+            ),
+        });
+    }
+    if !name.starts_with(|c: char| c.is_ascii_lowercase()) {
+        return Some("names must start with a letter".to_string());
+    }
+    if name.ends_with(['-', '_']) || underscore.contains("__") {
+        return Some("separators must sit between words".to_string());
+    }
+    if RUST_KEYWORDS.contains(&underscore.as_str()) {
+        return Some(format!("'{}' is a Rust keyword", name));
+    }
+    if RESERVED_NAMES.contains(&underscore.as_str()) {
+        return Some(format!("'{}' is a reserved crate name", name));
+    }
+    if WINDOWS_RESERVED.contains(&name) {
+        return Some(format!("'{}' is a reserved file name on Windows", name));
+    }
+    None
+}
+
 /// Validates that the project directory doesn't already exist
 ///
 /// # Arguments
-/// * `project_name` - The name of the project directory to validate
+/// * `project_dir` - The project directory to validate
 ///
 /// # Returns
 /// Ok(()) if the directory doesn't exist, Err if it does
-pub fn validate_project_directory(project_name: &str) -> Result<()> {
-    let project_dir = Path::new(project_name);
+pub fn validate_project_directory(project_dir: &Path) -> Result<()> {
     if project_dir.exists() {
         return Err(anyhow::anyhow!(
             "Directory '{}' already exists",
-            project_name
+            project_dir.display()
         ));
     }
     Ok(())
 }
 
-/// Validates that the template directory exists
+/// Validates that a project can be created inside a directory
 ///
 /// # Arguments
-/// * `template_dir` - Path to the template directory
+/// * `parent` - Directory the project directory will be created in
 ///
 /// # Returns
-/// Ok(()) if the directory exists, Err if it doesn't
-pub fn validate_template_directory(template_dir: &Path) -> Result<()> {
-    if !template_dir.exists() {
-        return Err(anyhow::anyhow!("Template directory not found"));
+/// Ok(()) if the directory exists and is writable, Err otherwise
+pub fn validate_parent_directory(parent: &Path) -> Result<()> {
+    // `Path::new("name").parent()` is empty and means the current directory
+    let parent = match parent.as_os_str().is_empty() {
+        true => Path::new("."),
+        false => parent,
+    };
+    if !parent.exists() {
+        return Err(anyhow::anyhow!(
+            "Parent directory '{}' does not exist",
+            parent.display()
+        ));
     }
-    Ok(())
+    if !parent.is_dir() {
+        return Err(anyhow::anyhow!(
+            "Parent path '{}' is not a directory",
+            parent.display()
+        ));
+    }
+
+    is_writable(parent).map_err(|e| {
+        anyhow::anyhow!(
+            "Parent directory '{}' is not writable: {}",
+            parent.display(),
+            e
+        )
+    })
 }
 
-/// Determines if a file should be processed as a template based on its extension
+/// Checks that entries can be created in a directory, without writing to it
 ///
-/// # Arguments
-/// * `file_path` - Path to the file to check
-///
-/// # Returns
-/// true if the file should be templated, false otherwise
-pub fn should_template_file(file_path: &Path) -> bool {
-    match file_path.extension().and_then(|e| e.to_str()) {
-        Some("rs") | Some("toml") | Some("md") | Some("json") | Some("txt") => true,
-        None => {
-            // Files without extension like LICENSE
-            matches!(
-                file_path.file_name().and_then(|f| f.to_str()),
-                Some("LICENSE") | Some("README")
-            )
-        }
-        _ => false,
+/// `access(2)` accounts for ACLs and read-only mounts, which permission bits
+/// do not, and leaves the directory untouched so `--dry-run` writes nothing.
+#[cfg(unix)]
+fn is_writable(dir: &Path) -> std::io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(dir.as_os_str().as_bytes())?;
+    // SAFETY: `path` is a valid NUL-terminated string for the whole call
+    match unsafe { libc::access(path.as_ptr(), libc::W_OK | libc::X_OK) } {
+        0 => Ok(()),
+        _ => Err(std::io::Error::last_os_error()),
     }
 }
 
-/// Should skip copying certain files during template processing
+#[cfg(not(unix))]
+fn is_writable(dir: &Path) -> std::io::Result<()> {
+    match std::fs::metadata(dir)?.permissions().readonly() {
+        true => Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "the directory is read-only",
+        )),
+        false => Ok(()),
+    }
+}
+
+/// Validates that the template source can be used for scaffolding
+///
+/// Custom templates must be an existing, readable directory with a
+/// `Cargo.toml` at its root.
 ///
 /// # Arguments
-/// * `file_name` - Name of the file to check
+/// * `source` - The template source to validate
 ///
 /// # Returns
-/// true if the file should be skipped, false otherwise
-pub fn should_skip_file(file_name: &str) -> bool {
-    file_name == "Cargo.lock" || file_name.ends_with(".lock") || file_name == "target"
+/// Ok(()) if the template is usable, Err describing the problem otherwise
+pub fn validate_template_directory(source: &TemplateSource) -> Result<()> {
+    if let TemplateSource::Local(root) = source {
+        if !root.exists() {
+            return Err(anyhow::anyhow!(
+                "Template directory '{}' not found",
+                root.display()
+            ));
+        }
+        if !root.is_dir() {
+            return Err(anyhow::anyhow!(
+                "Template path '{}' is not a directory",
+                root.display()
+            ));
+        }
+    }
+
+    let entries = source.read_dir(Path::new(""))?;
+    if entries.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Template directory '{}' is empty",
+            source.describe()
+        ));
+    }
+    if !entries
+        .iter()
+        .any(|entry| !entry.is_dir && entry.path == Path::new("Cargo.toml"))
+    {
+        return Err(anyhow::anyhow!(
+            "Template directory '{}' has no Cargo.toml at its root",
+            source.describe()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_program_names() {
+        for name in ["counter", "my-counter", "my_counter2", "foo-keypair"] {
+            assert!(validate_program_name(name).is_ok(), "'{}'", name);
+        }
+    }
+
+    #[test]
+    fn test_invalid_program_names() {
+        // (name, suggested correction)
+        let cases: &[(&str, Option<&str>)] = &[
+            ("2fast", Some("fast")),
+            ("self", Some("self-program")),
+            ("my counter", Some("my-counter")),
+            ("Foo--bar", Some("foo-bar")),
+            ("core", Some("core-program")),
+            ("std", Some("std-program")),
+            ("test", Some("test-program")),
+            ("pinocchio", Some("pinocchio-program")),
+            ("MyCounter", Some("my-counter")),
+            ("counter-", Some("counter")),
+            ("con", Some("con-program")),
+            ("", None),
+            ("123", None),
+        ];
+        for (name, suggestion) in cases {
+            let error = validate_program_name(name).unwrap_err().to_string();
+            assert_eq!(
+                suggest_program_name(name).as_deref(),
+                *suggestion,
+                "suggestion for '{}'",
+                name
+            );
+            if let Some(suggestion) = suggestion {
+                assert!(
+                    error.ends_with(&format!("try '{}'", suggestion)),
+                    "{}",
+                    error
+                );
+                assert!(
+                    validate_program_name(suggestion).is_ok(),
+                    "'{}'",
+                    suggestion
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_long_program_name_is_truncated() {
+        let name = "a".repeat(MAX_NAME_LENGTH + 1);
+        assert!(validate_program_name(&name).is_err());
+        assert_eq!(
+            suggest_program_name(&name),
+            Some("a".repeat(MAX_NAME_LENGTH))
+        );
+    }
 }
@@ -0,0 +1,258 @@
+use anyhow::Result;
+use console::style;
+use indicatif::{HumanCount, HumanDuration, ProgressBar, ProgressStyle};
+use solana_sdk::signature::{Keypair, Signer};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Characters of the base58 alphabet used by Solana addresses
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Keypairs generated by a thread between two updates of the shared counter
+const BATCH_SIZE: u64 = 256;
+
+/// Prefix and/or suffix a vanity program ID must have
+#[derive(Debug, Clone)]
+pub struct VanityPattern {
+    prefix: String,
+    suffix: String,
+    ignore_case: bool,
+}
+
+impl VanityPattern {
+    /// Creates a pattern, checking it can occur in a base58 address
+    ///
+    /// # Arguments
+    /// * `prefix` - Required start of the address
+    /// * `suffix` - Required end of the address
+    /// * `ignore_case` - Whether letters match regardless of case
+    ///
+    /// # Returns
+    /// The pattern, or None if neither a prefix nor a suffix is given
+    pub fn new(
+        prefix: Option<String>,
+        suffix: Option<String>,
+        ignore_case: bool,
+    ) -> Result<Option<Self>> {
+        if prefix.is_none() && suffix.is_none() {
+            return Ok(None);
+        }
+        let pattern = VanityPattern {
+            prefix: prefix.unwrap_or_default(),
+            suffix: suffix.unwrap_or_default(),
+            ignore_case,
+        };
+
+        for c in pattern.prefix.chars().chain(pattern.suffix.chars()) {
+            if pattern.variants(c) == 0 {
+                return Err(anyhow::anyhow!(
+                    "'{}' never appears in a program ID, base58 excludes 0, O, I and l",
+                    c
+                ));
+            }
+        }
+        // Addresses are 32 to 44 characters long
+        if pattern.prefix.len() + pattern.suffix.len() > 32 {
+            return Err(anyhow::anyhow!(
+                "A vanity prefix and suffix can be 32 characters long at most"
+            ));
+        }
+        Ok(Some(pattern))
+    }
+
+    /// Returns true if the address has the wanted prefix and suffix
+    pub fn matches(&self, address: &str) -> bool {
+        if self.ignore_case {
+            let address = address.to_ascii_lowercase();
+            address.starts_with(&self.prefix.to_ascii_lowercase())
+                && address.ends_with(&self.suffix.to_ascii_lowercase())
+        } else {
+            address.starts_with(&self.prefix) && address.ends_with(&self.suffix)
+        }
+    }
+
+    /// Expected number of keypairs to generate before one matches
+    ///
+    /// Every address character is treated as uniformly distributed over the
+    /// base58 alphabet, which is close enough for an estimate.
+    pub fn expected_attempts(&self) -> f64 {
+        self.prefix
+            .chars()
+            .chain(self.suffix.chars())
+            .map(|c| 58.0 / self.variants(c) as f64)
+            .product()
+    }
+
+    /// Human readable description, e.g. `abc…` or `…xyz (any case)`
+    pub fn describe(&self) -> String {
+        let mut description = match (self.prefix.is_empty(), self.suffix.is_empty()) {
+            (false, true) => format!("{}…", self.prefix),
+            (true, false) => format!("…{}", self.suffix),
+            _ => format!("{}…{}", self.prefix, self.suffix),
+        };
+        if self.ignore_case {
+            description.push_str(" (any case)");
+        }
+        description
+    }
+
+    /// Number of base58 characters a pattern character matches
+    fn variants(&self, c: char) -> usize {
+        if self.ignore_case {
+            BASE58_ALPHABET
+                .chars()
+                .filter(|a| a.eq_ignore_ascii_case(&c))
+                .count()
+        } else {
+            usize::from(BASE58_ALPHABET.contains(c))
+        }
+    }
+}
+
+/// Generates keypairs on every core until one matches the pattern
+///
+/// # Arguments
+/// * `pattern` - Prefix and suffix the program ID must have
+/// * `timeout` - Give up after this long, None to grind until found
+///
+/// # Returns
+/// The matching keypair, or Err if the timeout expired first
+pub fn grind_keypair(pattern: &VanityPattern, timeout: Option<Duration>) -> Result<Keypair> {
+    let expected = pattern.expected_attempts();
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+
+    let progress = ProgressBar::new(expected.min(u64::MAX as f64) as u64);
+    progress.set_style(
+        ProgressStyle::with_template(
+            "{spinner:.cyan} Grinding {prefix} [{bar:30.cyan/blue}] {msg} [{elapsed}]",
+        )?
+        .progress_chars("=> "),
+    );
+    progress.set_prefix(pattern.describe());
+    progress.enable_steady_tick(Duration::from_millis(80));
+
+    let found = Mutex::new(None);
+    let done = AtomicBool::new(false);
+    let attempts = AtomicU64::new(0);
+    let start = Instant::now();
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    for _ in 0..BATCH_SIZE {
+                        let keypair = Keypair::new();
+                        if pattern.matches(&keypair.pubkey().to_string()) {
+                            done.store(true, Ordering::Relaxed);
+                            found.lock().unwrap().get_or_insert(keypair);
+                            break;
+                        }
+                    }
+                    attempts.fetch_add(BATCH_SIZE, Ordering::Relaxed);
+                }
+            });
+        }
+
+        while !done.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(100));
+            let elapsed = start.elapsed();
+            let count = attempts.load(Ordering::Relaxed);
+            let rate = count as f64 / elapsed.as_secs_f64().max(0.001);
+            let remaining = (expected - count as f64).max(0.0) / rate.max(1.0);
+            progress.set_position(count);
+            progress.set_message(format!(
+                "{} attempts, {}/s, ~{} left",
+                HumanCount(count),
+                HumanCount(rate as u64),
+                HumanDuration(Duration::from_secs_f64(remaining.min(1e9)))
+            ));
+            if timeout.is_some_and(|timeout| elapsed >= timeout) {
+                done.store(true, Ordering::Relaxed);
+            }
+        }
+    });
+    progress.finish_and_clear();
+
+    let count = attempts.load(Ordering::Relaxed);
+    let keypair = found.into_inner().unwrap().ok_or_else(|| {
+        anyhow::anyhow!(
+            "No program ID matching {} found within {} ({} attempts), \
+             try a shorter pattern, --vanity-ignore-case or a longer --vanity-timeout",
+            pattern.describe(),
+            HumanDuration(start.elapsed()),
+            HumanCount(count)
+        )
+    })?;
+    println!(
+        "{} Found vanity program ID after {} attempts in {}",
+        style("🎯").green(),
+        HumanCount(count),
+        HumanDuration(start.elapsed())
+    );
+    Ok(keypair)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(prefix: &str, suffix: &str, ignore_case: bool) -> Result<Option<VanityPattern>> {
+        let non_empty = |s: &str| Some(s.to_string()).filter(|s| !s.is_empty());
+        VanityPattern::new(non_empty(prefix), non_empty(suffix), ignore_case)
+    }
+
+    #[test]
+    fn test_excluded_characters() {
+        for c in ["0", "O", "I", "l"] {
+            assert!(pattern(c, "", false).is_err(), "prefix '{}'", c);
+            assert!(pattern("", c, false).is_err(), "suffix '{}'", c);
+        }
+        // Letters match their other case, which base58 has, but 0 never appears
+        for c in ["O", "I", "l"] {
+            assert!(pattern(c, "", true).unwrap().is_some(), "'{}'", c);
+        }
+        assert!(pattern("0", "", true).is_err());
+        assert!(pattern("", "", false).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_length_limit() {
+        assert!(pattern(&"a".repeat(32), "", false).unwrap().is_some());
+        assert!(pattern(&"a".repeat(20), &"b".repeat(12), false)
+            .unwrap()
+            .is_some());
+        assert!(pattern(&"a".repeat(33), "", false).is_err());
+        assert!(pattern(&"a".repeat(20), &"b".repeat(13), false).is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let exact = pattern("Ab", "9z", false).unwrap().unwrap();
+        assert!(exact.matches("Abc123xyz9z"));
+        assert!(!exact.matches("abc123xyz9z"));
+        assert!(!exact.matches("Abc123xyz9Z"));
+
+        let any_case = pattern("Ab", "9z", true).unwrap().unwrap();
+        for address in ["Abc123xyz9z", "abc123xyz9z", "ABc123xyz9Z"] {
+            assert!(any_case.matches(address), "{}", address);
+        }
+        assert!(!any_case.matches("Acb123xyz9z"));
+    }
+
+    #[test]
+    fn test_expected_attempts() {
+        let attempts = |prefix, suffix, ignore_case| {
+            pattern(prefix, suffix, ignore_case)
+                .unwrap()
+                .unwrap()
+                .expected_attempts()
+        };
+        assert_eq!(attempts("a", "", false), 58.0);
+        assert_eq!(attempts("ab", "c", false), 58.0 * 58.0 * 58.0);
+        // Both cases of a letter match, except where base58 only has one
+        assert_eq!(attempts("ab", "", true), 29.0 * 29.0);
+        assert_eq!(attempts("o", "1", true), 58.0 * 58.0);
+    }
+}
@@ -16,14 +16,7 @@ use {
     },
 };
 
-pub const PROGRAM: Pubkey = Pubkey::new_from_array(ID);
-pub const AUTHORITY: Pubkey = pubkey!("Co11111111111111111111111111111111111111111");
-
-pub fn mollusk() -> Mollusk {
-    let mollusk = Mollusk::new(&PROGRAM, "../../target/deploy/{{program_name_underscore}}");
-    mollusk
-}
-
+{% include "mollusk.rs" %}
 /// Helper function to create instruction data for increase
 fn create_increase_instruction_data(amount: u64) -> Vec<u8> {
     let mut data = vec![*Increase::DISCRIMINATOR];
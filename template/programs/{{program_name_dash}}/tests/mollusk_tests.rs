@@ -18,14 +18,7 @@ use solana_sdk::{
 extern crate alloc;
 use alloc::vec;
 
-pub const PROGRAM: Pubkey = Pubkey::new_from_array(ID);
-pub const AUTHORITY: Pubkey = pubkey!("Co11111111111111111111111111111111111111111");
-
-pub fn mollusk() -> Mollusk {
-    let mollusk = Mollusk::new(&PROGRAM, "../../target/deploy/{{program_name_underscore}}");
-    mollusk
-}
-
+{% include "mollusk.rs" %}
 #[test]
 fn test_create_counter() {
     let mollusk = mollusk();